@@ -0,0 +1,244 @@
+use core::ops::Range;
+
+use polymesh_api::client::{sp_weights::Weight, AccountId, Error, ExtrinsicResult, Result, Signer};
+use polymesh_api::types::{
+  runtime::{events::UtilityEvent, RuntimeEvent},
+  sp_runtime::DispatchError,
+};
+use polymesh_api::{Api, TransactionResults, WrappedCall};
+
+/// Which utility batch call to use.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BatchMode {
+  /// `utility.batch`: Stop at the first failed call.  Earlier calls are not reverted.
+  Batch,
+  /// `utility.batch_all`: All calls are reverted if any call fails.
+  #[default]
+  BatchAll,
+  /// `utility.force_batch`: Failed calls don't stop the batch.
+  ForceBatch,
+}
+
+/// The result of one call in a batch.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BatchCallResult {
+  /// The call was executed successfully.
+  Success,
+  /// The call failed.
+  Failed(DispatchError),
+  /// The call wasn't executed, because an earlier call in the batch failed.
+  NotExecuted,
+  /// The batch failed (`batch_all`) and all calls in it were reverted.
+  Reverted(DispatchError),
+}
+
+impl BatchCallResult {
+  pub fn is_success(&self) -> bool {
+    matches!(self, Self::Success)
+  }
+}
+
+/// Build and execute utility batches from many calls.
+///
+/// The calls are split into multiple batches, if needed, to stay within the
+/// limits for the number of calls, encoded size and weight of each batch.
+pub struct BatchBuilder {
+  api: Api,
+  mode: BatchMode,
+  calls: Vec<WrappedCall>,
+  max_calls: Option<usize>,
+  max_size: Option<usize>,
+  max_weight: Option<Weight>,
+}
+
+impl BatchBuilder {
+  pub fn new(api: &Api) -> Self {
+    Self {
+      api: api.clone(),
+      mode: BatchMode::default(),
+      calls: Vec::new(),
+      max_calls: None,
+      max_size: None,
+      max_weight: None,
+    }
+  }
+
+  pub fn mode(mut self, mode: BatchMode) -> Self {
+    self.mode = mode;
+    self
+  }
+
+  /// Maximum number of calls in each batch.
+  pub fn max_calls(mut self, max_calls: usize) -> Self {
+    self.max_calls = Some(max_calls);
+    self
+  }
+
+  /// Maximum encoded size (in bytes) of the calls in each batch.
+  pub fn max_size(mut self, max_size: usize) -> Self {
+    self.max_size = Some(max_size);
+    self
+  }
+
+  /// Maximum estimated weight of the calls in each batch.
+  ///
+  /// The weight of each call is estimated with `payment_queryInfo`.
+  pub fn max_weight(mut self, max_weight: Weight) -> Self {
+    self.max_weight = Some(max_weight);
+    self
+  }
+
+  pub fn push(&mut self, call: WrappedCall) {
+    self.calls.push(call);
+  }
+
+  pub fn call(mut self, call: WrappedCall) -> Self {
+    self.calls.push(call);
+    self
+  }
+
+  pub fn calls(mut self, calls: impl IntoIterator<Item = WrappedCall>) -> Self {
+    self.calls.extend(calls);
+    self
+  }
+
+  pub fn len(&self) -> usize {
+    self.calls.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.calls.is_empty()
+  }
+
+  /// Split the calls into batches.  Returns the range of calls in each batch.
+  ///
+  /// `account` is used to estimate the call weights.
+  pub async fn split(&self, account: AccountId) -> Result<Vec<Range<usize>>> {
+    let mut batches = Vec::new();
+    let mut start = 0;
+    let mut size = 0;
+    let mut weight = Weight::zero();
+    for (idx, call) in self.calls.iter().enumerate() {
+      let call_size = call.encoded().0.len();
+      let call_weight = match self.max_weight {
+        Some(_) => call.estimate_fee(account).await?.weight,
+        None => Weight::zero(),
+      };
+      let is_full = self.max_calls.is_some_and(|max| idx - start >= max)
+        || self.max_size.is_some_and(|max| size + call_size > max)
+        || self
+          .max_weight
+          .is_some_and(|max| weight.saturating_add(call_weight).any_gt(max));
+      if is_full && idx > start {
+        batches.push(start..idx);
+        start = idx;
+        size = 0;
+        weight = Weight::zero();
+      }
+      size += call_size;
+      weight = weight.saturating_add(call_weight);
+    }
+    if start < self.calls.len() {
+      batches.push(start..self.calls.len());
+    }
+    Ok(batches)
+  }
+
+  fn batch_call(&self, range: Range<usize>) -> Result<WrappedCall> {
+    let calls = self.calls[range]
+      .iter()
+      .map(|call| call.runtime_call().clone())
+      .collect();
+    let utility = self.api.call().utility();
+    match self.mode {
+      BatchMode::Batch => utility.batch(calls),
+      BatchMode::BatchAll => utility.batch_all(calls),
+      BatchMode::ForceBatch => utility.force_batch(calls),
+    }
+  }
+
+  /// Sign and submit the batches, but don't wait for them to execute.
+  pub async fn submit_and_watch(&self, signer: &mut impl Signer) -> Result<Vec<BatchResults>> {
+    let mut results = Vec::new();
+    for range in self.split(signer.account()).await? {
+      let len = range.len();
+      let res = self.batch_call(range)?.submit_and_watch(signer).await?;
+      results.push(BatchResults {
+        mode: self.mode,
+        len,
+        res,
+      });
+    }
+    Ok(results)
+  }
+
+  /// Sign, submit and execute the batches.  Returns the result of each call.
+  pub async fn execute(&self, signer: &mut impl Signer) -> Result<Vec<BatchCallResult>> {
+    let mut results = Vec::with_capacity(self.calls.len());
+    for mut batch in self.submit_and_watch(signer).await? {
+      results.extend(batch.call_results().await?);
+    }
+    Ok(results)
+  }
+}
+
+/// The transaction results of one batch.
+pub struct BatchResults {
+  mode: BatchMode,
+  len: usize,
+  pub res: TransactionResults,
+}
+
+impl BatchResults {
+  /// Number of calls in the batch.
+  pub fn len(&self) -> usize {
+    self.len
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.len == 0
+  }
+
+  /// Wait for the batch to execute and map the utility events to each call's result.
+  ///
+  /// Calls that emit their own utility events (nested batches) are not supported.
+  pub async fn call_results(&mut self) -> Result<Vec<BatchCallResult>> {
+    if let Some(ExtrinsicResult::Failed(_, err)) = self.res.extrinsic_result().await? {
+      // The whole batch failed.
+      return Ok(vec![BatchCallResult::Reverted(err.clone()); self.len]);
+    }
+    let events = self
+      .res
+      .events()
+      .await?
+      .ok_or_else(|| Error::ExtrinsicError("Failed to get batch events".into()))?;
+    let mut results = Vec::with_capacity(self.len);
+    for rec in &events.0 {
+      match &rec.event {
+        RuntimeEvent::Utility(UtilityEvent::ItemCompleted) => {
+          results.push(BatchCallResult::Success);
+        }
+        RuntimeEvent::Utility(UtilityEvent::ItemFailed { error }) => {
+          results.push(BatchCallResult::Failed(error.clone()));
+        }
+        RuntimeEvent::Utility(UtilityEvent::BatchInterrupted { index, error }) => {
+          results.truncate(*index as usize);
+          results.push(BatchCallResult::Failed(error.clone()));
+          break;
+        }
+        _ => (),
+      }
+    }
+    if self.mode == BatchMode::Batch {
+      results.resize(self.len, BatchCallResult::NotExecuted);
+    }
+    if results.len() != self.len {
+      return Err(Error::ExtrinsicError(format!(
+        "Expected {} batch results, got {}",
+        self.len,
+        results.len()
+      )));
+    }
+    Ok(results)
+  }
+}
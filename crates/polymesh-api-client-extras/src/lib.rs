@@ -16,6 +16,15 @@ use polymesh_api::TransactionResults;
 mod user;
 pub use user::*;
 
+mod protocol_fee;
+pub use protocol_fee::*;
+
+mod batch;
+pub use batch::*;
+
+mod multisig;
+pub use multisig::*;
+
 pub const ONE_POLYX: u128 = 1_000_000;
 
 pub type Moment = u64;
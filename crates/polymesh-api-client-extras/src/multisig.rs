@@ -0,0 +1,251 @@
+use std::collections::BTreeMap;
+
+use polymesh_api::client::{sp_weights::Weight, AccountId, Error, EventRecords, Result, Signer};
+use polymesh_api::types::{
+  polymesh_primitives::{
+    multisig::{ProposalState, ProposalVoteCount},
+    secondary_key::Permissions,
+  },
+  runtime::{
+    events::{IdentityEvent, MultiSigEvent},
+    RuntimeEvent,
+  },
+  sp_runtime::DispatchError,
+};
+use polymesh_api::{Api, WrappedCall};
+
+/// The status of a multisig proposal after a transaction.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ProposalStatus {
+  /// Waiting for more votes.
+  Pending,
+  /// The proposal was approved and executed.
+  Executed(core::result::Result<(), DispatchError>),
+  /// The proposal was rejected.
+  Rejected,
+}
+
+impl ProposalStatus {
+  /// Get the status of `proposal_id` from the events of a multisig transaction.
+  pub fn from_events(
+    events: &EventRecords<RuntimeEvent>,
+    multisig: AccountId,
+    proposal_id: u64,
+  ) -> Self {
+    for rec in &events.0 {
+      match &rec.event {
+        RuntimeEvent::MultiSig(MultiSigEvent::ProposalExecuted {
+          multisig: ms,
+          proposal_id: id,
+          result,
+          ..
+        }) if *ms == multisig && *id == proposal_id => {
+          return Self::Executed(result.clone());
+        }
+        RuntimeEvent::MultiSig(MultiSigEvent::ProposalRejected {
+          multisig: ms,
+          proposal_id: id,
+          ..
+        }) if *ms == multisig && *id == proposal_id => {
+          return Self::Rejected;
+        }
+        _ => (),
+      }
+    }
+    Self::Pending
+  }
+
+  /// Returns the dispatch error if the proposal was executed and failed.
+  pub fn error(&self) -> Option<&DispatchError> {
+    match self {
+      Self::Executed(Err(err)) => Some(err),
+      _ => None,
+    }
+  }
+}
+
+/// A multisig proposal.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MultiSigProposal {
+  pub multisig: AccountId,
+  pub proposal_id: u64,
+  pub status: ProposalStatus,
+}
+
+/// Helper for the MultiSig pallet workflow: create a multisig, add signers,
+/// propose calls and vote on the proposals.
+#[derive(Clone)]
+pub struct MultiSigHelper {
+  api: Api,
+  pub multisig: AccountId,
+  /// `JoinMultiSig` authorizations of signers that haven't joined yet.
+  pub pending_signers: BTreeMap<AccountId, u64>,
+}
+
+impl MultiSigHelper {
+  /// Helper for an existing multisig.
+  pub fn new(api: &Api, multisig: AccountId) -> Self {
+    Self {
+      api: api.clone(),
+      multisig,
+      pending_signers: Default::default(),
+    }
+  }
+
+  /// Create a new multisig.
+  ///
+  /// The `signers` need to join the multisig with [`Self::accept_signer`] before they can vote.
+  pub async fn create(
+    api: &Api,
+    creator: &mut impl Signer,
+    signers: Vec<AccountId>,
+    sigs_required: u64,
+    permissions: Option<Permissions>,
+  ) -> Result<Self> {
+    let mut res = api
+      .call()
+      .multi_sig()
+      .create_multisig(signers.clone(), sigs_required, permissions)?
+      .execute(creator)
+      .await?;
+    let mut multisig = None;
+    let mut pending_signers = BTreeMap::new();
+    if let Some(events) = res.events().await? {
+      for rec in &events.0 {
+        match &rec.event {
+          RuntimeEvent::MultiSig(MultiSigEvent::MultiSigCreated { multisig: ms, .. }) => {
+            multisig = Some(*ms);
+          }
+          RuntimeEvent::Identity(IdentityEvent::AuthorizationAdded(
+            _,
+            _,
+            Some(key),
+            auth_id,
+            _,
+            _,
+          )) if signers.contains(key) => {
+            pending_signers.insert(*key, *auth_id);
+          }
+          _ => (),
+        }
+      }
+    }
+    let multisig =
+      multisig.ok_or_else(|| Error::ExtrinsicError("Missing MultiSigCreated event".into()))?;
+    Ok(Self {
+      api: api.clone(),
+      multisig,
+      pending_signers,
+    })
+  }
+
+  /// Join the multisig as `signer`, by accepting its pending authorization.
+  pub async fn accept_signer(&mut self, signer: &mut impl Signer) -> Result<()> {
+    let account = signer.account();
+    let auth_id = self.pending_signers.get(&account).copied().ok_or_else(|| {
+      Error::ExtrinsicError(format!("No pending multisig authorization for {account}"))
+    })?;
+    self
+      .api
+      .call()
+      .multi_sig()
+      .accept_multisig_signer(auth_id)?
+      .execute(signer)
+      .await?;
+    self.pending_signers.remove(&account);
+    Ok(())
+  }
+
+  /// Propose `call` to be executed by the multisig.  The proposer's vote is counted
+  /// as an approval, so the proposal can be executed immediately.
+  pub async fn propose(
+    &self,
+    signer: &mut impl Signer,
+    call: WrappedCall,
+    expiry: Option<u64>,
+  ) -> Result<MultiSigProposal> {
+    let mut res = self
+      .api
+      .call()
+      .multi_sig()
+      .create_proposal(self.multisig, call.into_runtime_call(), expiry)?
+      .execute(signer)
+      .await?;
+    let events = res
+      .events()
+      .await?
+      .ok_or_else(|| Error::ExtrinsicError("Failed to get proposal events".into()))?;
+    let proposal_id = events
+      .0
+      .iter()
+      .find_map(|rec| match &rec.event {
+        RuntimeEvent::MultiSig(MultiSigEvent::ProposalAdded {
+          multisig,
+          proposal_id,
+          ..
+        }) if *multisig == self.multisig => Some(*proposal_id),
+        _ => None,
+      })
+      .ok_or_else(|| Error::ExtrinsicError("Missing ProposalAdded event".into()))?;
+    Ok(MultiSigProposal {
+      multisig: self.multisig,
+      proposal_id,
+      status: ProposalStatus::from_events(events, self.multisig, proposal_id),
+    })
+  }
+
+  /// Approve a proposal.  Returns the proposal status after the vote.
+  pub async fn approve(
+    &self,
+    signer: &mut impl Signer,
+    proposal_id: u64,
+    max_weight: Option<Weight>,
+  ) -> Result<ProposalStatus> {
+    let mut res = self
+      .api
+      .call()
+      .multi_sig()
+      .approve(self.multisig, proposal_id, max_weight)?
+      .execute(signer)
+      .await?;
+    Ok(match res.events().await? {
+      Some(events) => ProposalStatus::from_events(events, self.multisig, proposal_id),
+      None => ProposalStatus::Pending,
+    })
+  }
+
+  /// Reject a proposal.  Returns the proposal status after the vote.
+  pub async fn reject(&self, signer: &mut impl Signer, proposal_id: u64) -> Result<ProposalStatus> {
+    let mut res = self
+      .api
+      .call()
+      .multi_sig()
+      .reject(self.multisig, proposal_id)?
+      .execute(signer)
+      .await?;
+    Ok(match res.events().await? {
+      Some(events) => ProposalStatus::from_events(events, self.multisig, proposal_id),
+      None => ProposalStatus::Pending,
+    })
+  }
+
+  /// Get the on-chain state of a proposal.
+  pub async fn proposal_state(&self, proposal_id: u64) -> Result<Option<ProposalState<u64>>> {
+    self
+      .api
+      .query()
+      .multi_sig()
+      .proposal_states(self.multisig, proposal_id)
+      .await
+  }
+
+  /// Get the number of approvals and rejections of a proposal.
+  pub async fn vote_count(&self, proposal_id: u64) -> Result<Option<ProposalVoteCount>> {
+    self
+      .api
+      .query()
+      .multi_sig()
+      .proposal_vote_counts(self.multisig, proposal_id)
+      .await
+  }
+}
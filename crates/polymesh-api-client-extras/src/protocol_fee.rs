@@ -0,0 +1,38 @@
+use polymesh_api::client::{BlockHash, Result};
+use polymesh_api::types::polymesh_common_utilities::protocol_fee::ProtocolOp;
+use polymesh_api::Api;
+
+/// Get the protocol fee charged for `op`.
+///
+/// See [`get_protocol_fees`].
+pub async fn get_protocol_fee(api: &Api, op: ProtocolOp, at: Option<BlockHash>) -> Result<u128> {
+  get_protocol_fees(api, &[op], at).await
+}
+
+/// Get the total protocol fee charged for `ops`.
+///
+/// The fee is the sum of the `ProtocolFee::BaseFees` of each operation scaled by
+/// the `ProtocolFee::Coefficient`.  Protocol fees are charged on top of the
+/// transaction fee.
+pub async fn get_protocol_fees(
+  api: &Api,
+  ops: &[ProtocolOp],
+  at: Option<BlockHash>,
+) -> Result<u128> {
+  let query = match at {
+    Some(block) => api.query_at(block),
+    None => api.query(),
+  };
+  let protocol_fee = query.protocol_fee();
+  let mut base_fee = 0u128;
+  for op in ops {
+    base_fee = base_fee.saturating_add(protocol_fee.base_fees(op.clone()).await?);
+  }
+  let coefficient = protocol_fee.coefficient().await?;
+  Ok(
+    base_fee
+      .saturating_mul(coefficient.0 as u128)
+      .checked_div(coefficient.1 as u128)
+      .unwrap_or_default(),
+  )
+}
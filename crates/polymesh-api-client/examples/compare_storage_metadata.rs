@@ -36,23 +36,34 @@ pub struct Metadata {
 
 impl Metadata {
   pub fn from_v14(md: RuntimeMetadataV14) -> Self {
+    let pallets = md.pallets.into_iter().map(|p| (p.name, p.storage));
+    Self::from_pallets(md.types, pallets)
+  }
+
+  pub fn from_v15(md: frame_metadata::v15::RuntimeMetadataV15) -> Self {
+    let pallets = md.pallets.into_iter().map(|p| (p.name, p.storage));
+    Self::from_pallets(md.types, pallets)
+  }
+
+  fn from_pallets(
+    types: PortableRegistry,
+    pallets: impl Iterator<Item = (String, Option<PalletStorageMetadata<PortableForm>>)>,
+  ) -> Self {
     let mut storage_prefix = HashMap::new();
     Self {
-      types: md.types,
-      storage: md
-        .pallets
-        .into_iter()
-        .filter_map(|p| {
-          p.storage.map(|s| {
-            if let Some(old_pallet) = storage_prefix.insert(s.prefix.clone(), p.name.clone()) {
+      types,
+      storage: pallets
+        .filter_map(|(name, storage)| {
+          storage.map(|s| {
+            if let Some(old_pallet) = storage_prefix.insert(s.prefix.clone(), name.clone()) {
               log::error!(
                 "Duplicate storage prefix '{}' used by {} and {}",
                 s.prefix,
                 old_pallet,
-                p.name
+                name
               );
             }
-            (p.name.clone(), StorageMetadata::from_v14(s))
+            (name, StorageMetadata::from_v14(s))
           })
         })
         .collect(),
@@ -66,6 +77,7 @@ impl Metadata {
     let metadata = RuntimeMetadataPrefixed::decode(&mut buf.as_slice())?;
     match metadata.1 {
       RuntimeMetadata::V14(md) => Ok(Self::from_v14(md)),
+      RuntimeMetadata::V15(md) => Ok(Self::from_v15(md)),
       _ => {
         panic!("Unsupported metadata version: {:?}", metadata);
       }
@@ -0,0 +1,46 @@
+use std::env;
+use std::str::FromStr;
+
+use anyhow::Result;
+
+use polymesh_api_client::*;
+
+#[tokio::main]
+async fn main() -> Result<()> {
+  dotenv::dotenv().ok();
+  env_logger::init();
+
+  let url = env::args().nth(1).expect("Missing ws url");
+  let filename = env::args().nth(2).expect("Missing snapshot filename");
+  // Pallet name.  Use `all` to snapshot the whole chain.
+  let pallet = env::args().nth(3).unwrap_or_else(|| "all".into());
+  let block_hash = env::args()
+    .nth(4)
+    .and_then(|ref h| BlockHash::from_str(h).ok());
+
+  let client = Client::new(&url).await?;
+  let block_hash = match block_hash {
+    Some(hash) => hash,
+    None => client.get_finalized_block().await?,
+  };
+
+  let prefix = if pallet == "all" {
+    StorageKey(vec![])
+  } else {
+    pallet_storage_prefix(&pallet)
+  };
+  println!("Download storage of '{pallet}' at block: {block_hash:?}");
+  let snapshot = StorageSnapshot::download(&client, prefix, block_hash).await?;
+  snapshot.save(&filename)?;
+  println!(
+    "Saved {} storage entries to file: {}",
+    snapshot.len(),
+    filename
+  );
+
+  // Reload the snapshot to check it.
+  let loaded = StorageSnapshot::load(&filename)?;
+  assert_eq!(loaded.len(), snapshot.len());
+
+  Ok(())
+}
@@ -168,6 +168,40 @@ pub struct StorageKey(
   #[cfg_attr(feature = "serde", serde(with = "impl_serde::serialize"))] pub Vec<u8>,
 );
 
+/// Storage read proof returned by `state_getReadProof`.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub struct ReadProof {
+  pub at: BlockHash,
+  pub proof: Vec<StorageData>,
+}
+
+impl ReadProof {
+  /// Verify the proof against the block's `state_root` and return the value of `key`.
+  ///
+  /// Fails if the proof doesn't contain the trie nodes needed to prove the
+  /// value (or absence) of `key`.
+  pub fn verify(&self, state_root: &BlockHash, key: &StorageKey) -> Result<Option<StorageData>> {
+    use sp_runtime::traits::BlakeTwo256;
+    use sp_trie::{read_trie_value, LayoutV1, StorageProof};
+
+    let db = StorageProof::new(self.proof.iter().map(|node| node.0.clone()))
+      .into_memory_db::<BlakeTwo256>();
+    let value = read_trie_value::<LayoutV1<BlakeTwo256>, _>(&db, state_root, &key.0, None, None)
+      .map_err(|e| Error::StorageProofFailed(format!("{e:?}")))?;
+    Ok(value.map(StorageData))
+  }
+}
+
+/// Storage changes of a block from `state_subscribeStorage`.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct StorageChangeSet {
+  pub block: BlockHash,
+  pub changes: Vec<(StorageKey, Option<StorageData>)>,
+}
+
 #[derive(Clone, Debug, Default)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct AdditionalSigned {
@@ -272,25 +306,86 @@ impl From<Era> for sp_runtime::generic::Era {
   }
 }
 
+/// Mode of the `CheckMetadataHash` signed extension.
+///
+/// When enabled the metadata hash (RFC-78) is included in the signed payload.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Encode, Decode)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum MetadataHashMode {
+  Disabled,
+  Enabled,
+}
+
+/// Deserialize the `metadata_hash_mode` of `Extra`.
+///
+/// Older versions serialized the field as `metadata_hash: Option<Option<H256>>`, where
+/// `null` was used for both "no signed extension" and "disabled".
+#[cfg(feature = "serde")]
+mod metadata_hash_mode {
+  use super::*;
+
+  #[derive(Deserialize)]
+  #[serde(untagged)]
+  enum ModeOrHash {
+    Mode(MetadataHashMode),
+    Hash(#[allow(dead_code)] H256),
+  }
+
+  pub fn deserialize<'de, D>(d: D) -> Result<Option<MetadataHashMode>, D::Error>
+  where
+    D: serde::Deserializer<'de>,
+  {
+    Ok(match Option::<ModeOrHash>::deserialize(d)? {
+      Some(ModeOrHash::Mode(mode)) => Some(mode),
+      Some(ModeOrHash::Hash(_)) => Some(MetadataHashMode::Enabled),
+      // The `CheckMetadataHash` signed extension is only used by Polymesh v8.
+      #[cfg(feature = "polymesh_v8")]
+      None => Some(MetadataHashMode::Disabled),
+      #[cfg(not(feature = "polymesh_v8"))]
+      None => None,
+    })
+  }
+}
+
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Extra {
   era: sp_runtime::generic::Era,
   nonce: Compact<u32>,
   tip: Compact<u128>,
-  metadata_hash: Option<Option<H256>>,
+  #[cfg_attr(
+    feature = "serde",
+    serde(
+      alias = "metadata_hash",
+      default,
+      deserialize_with = "metadata_hash_mode::deserialize"
+    )
+  )]
+  metadata_hash_mode: Option<MetadataHashMode>,
 }
 
 impl Extra {
+  /// The `metadata_hash` is from [`AdditionalSigned::encode_metadata_hash`].
   pub fn new(era: Era, nonce: u32, metadata_hash: Option<Option<H256>>) -> Self {
     Self {
       era: era.into(),
       nonce: nonce.into(),
       tip: 0u128.into(),
-      metadata_hash,
+      metadata_hash_mode: metadata_hash.map(|hash| match hash {
+        Some(_) => MetadataHashMode::Enabled,
+        None => MetadataHashMode::Disabled,
+      }),
     }
   }
 
+  pub fn metadata_hash_mode(&self) -> Option<MetadataHashMode> {
+    self.metadata_hash_mode
+  }
+
+  pub fn era(&self) -> Era {
+    self.era.into()
+  }
+
   pub fn nonce(&self) -> u32 {
     self.nonce.0
   }
@@ -305,8 +400,8 @@ impl Encode for Extra {
     self.era.encode_to(output);
     self.nonce.encode_to(output);
     self.tip.encode_to(output);
-    if let Some(metadata_hash) = &self.metadata_hash {
-      metadata_hash.encode_to(output);
+    if let Some(mode) = &self.metadata_hash_mode {
+      mode.encode_to(output);
     }
   }
 }
@@ -317,14 +412,14 @@ impl Decode for Extra {
     let nonce = Decode::decode(input)?;
     let tip = Decode::decode(input)?;
     #[cfg(feature = "polymesh_v8")]
-    let metadata_hash = Some(Decode::decode(input)?);
+    let metadata_hash_mode = Some(Decode::decode(input)?);
     #[cfg(not(feature = "polymesh_v8"))]
-    let metadata_hash = None;
+    let metadata_hash_mode = None;
     Ok(Self {
       era,
       nonce,
       tip,
-      metadata_hash,
+      metadata_hash_mode,
     })
   }
 }
@@ -714,6 +809,39 @@ mod tests {
 
   use super::*;
 
+  /// `Extra` serialized by older versions, with the `metadata_hash` field.
+  #[cfg(feature = "serde")]
+  #[test]
+  fn test_extra_old_metadata_hash() -> Result<()> {
+    let extra = Extra::new(Era::immortal(), 1, None);
+    let mut json = serde_json::to_value(&extra)?;
+    let fields = json.as_object_mut().expect("Extra is a struct");
+    fields.remove("metadata_hash_mode");
+
+    fields.insert("metadata_hash".into(), serde_json::Value::Null);
+    let old: Extra = serde_json::from_value(json.clone())?;
+    #[cfg(feature = "polymesh_v8")]
+    assert_eq!(old.metadata_hash_mode(), Some(MetadataHashMode::Disabled));
+    #[cfg(not(feature = "polymesh_v8"))]
+    assert_eq!(old.metadata_hash_mode(), None);
+    assert_eq!(old.nonce(), 1);
+
+    let hash = serde_json::to_value(H256::repeat_byte(1))?;
+    json["metadata_hash"] = hash;
+    let old: Extra = serde_json::from_value(json)?;
+    assert_eq!(old.metadata_hash_mode(), Some(MetadataHashMode::Enabled));
+
+    // New format roundtrip.
+    let extra = Extra::new(Era::immortal(), 2, Some(Some(H256::zero())));
+    let json = serde_json::to_string(&extra)?;
+    let decoded: Extra = serde_json::from_str(&json)?;
+    assert_eq!(
+      decoded.metadata_hash_mode(),
+      Some(MetadataHashMode::Enabled)
+    );
+    Ok(())
+  }
+
   /// Test the `BytesPayload` signing and verification.
   #[tokio::test]
   async fn test_bytes_payload() -> Result<()> {
@@ -774,4 +902,40 @@ mod tests {
 
     Ok(())
   }
+
+  /// Test `ReadProof` verification against a trie root.
+  #[test]
+  fn test_read_proof_verify() -> Result<()> {
+    use sp_runtime::traits::BlakeTwo256;
+    use sp_trie::{LayoutV1, MemoryDB, TrieDBMutBuilder, TrieMut};
+
+    let mut db = MemoryDB::<BlakeTwo256>::default();
+    let mut root = BlockHash::default();
+    {
+      let mut trie = TrieDBMutBuilder::<LayoutV1<BlakeTwo256>>::new(&mut db, &mut root).build();
+      trie.insert(b"key1", b"value1")?;
+      trie.insert(b"key2", &[0xAA; 64])?;
+    }
+    let proof = ReadProof {
+      at: BlockHash::default(),
+      proof: db
+        .drain()
+        .into_values()
+        .map(|(node, _)| StorageData(node))
+        .collect(),
+    };
+
+    // Existing and missing keys.
+    let value = proof.verify(&root, &StorageKey(b"key1".to_vec()))?;
+    assert_eq!(value.map(|v| v.0), Some(b"value1".to_vec()));
+    let value = proof.verify(&root, &StorageKey(b"key3".to_vec()))?;
+    assert!(value.is_none());
+
+    // Wrong state root.
+    assert!(proof
+      .verify(&BlockHash::repeat_byte(1), &StorageKey(b"key1".to_vec()))
+      .is_err());
+
+    Ok(())
+  }
 }
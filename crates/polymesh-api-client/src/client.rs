@@ -1,10 +1,19 @@
 #[cfg(not(feature = "std"))]
 use alloc::{collections::btree_map::BTreeMap, sync::Arc};
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::Weak;
 #[cfg(feature = "std")]
-use std::{collections::BTreeMap, sync::Arc};
+use std::{
+  collections::BTreeMap,
+  sync::{Arc, RwLock},
+};
+#[cfg(not(target_arch = "wasm32"))]
+use tokio::sync::oneshot;
 
+#[cfg(not(target_arch = "wasm32"))]
+pub use crate::rpc::ReconnectConfig;
+pub use crate::rpc::{EndpointsConfig, RpcTransport, Subscription, TransportSubscription};
 use jsonrpsee::core::client::BatchResponse;
-pub use jsonrpsee::core::client::Subscription;
 use jsonrpsee::core::params::{ArrayParams, BatchRequestBuilder};
 use jsonrpsee::rpc_params;
 
@@ -22,9 +31,17 @@ use sp_std::prelude::*;
 #[cfg(feature = "type_info")]
 use frame_metadata::RuntimeMetadataPrefixed;
 
+#[cfg(feature = "metadata_hash")]
+use sp_core::H256;
+
 use crate::rpc::*;
 use crate::*;
 
+/// Number of storage keys requested per `state_getKeysPaged` call.
+const STORAGE_KEYS_PAGE_SIZE: usize = 1000;
+/// Number of storage values requested per `state_queryStorageAt` call.
+const STORAGE_VALUES_BATCH_SIZE: usize = 100;
+
 #[derive(Clone, Debug, Default)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
@@ -51,66 +68,260 @@ pub struct SystemProperties {
   pub token_symbol: String,
 }
 
-struct InnerClient {
-  rpc: RpcClient,
-  runtime_version: RuntimeVersion,
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub struct SystemHealth {
+  pub peers: u64,
+  pub is_syncing: bool,
+  pub should_have_peers: bool,
+}
+
+/// Get the `Pallet.call_name` of the runtime calls, keyed by pallet and call index.
+#[cfg(feature = "type_info")]
+fn metadata_call_names(metadata: &RuntimeMetadataPrefixed) -> BTreeMap<[u8; 2], String> {
+  use frame_metadata::RuntimeMetadata;
+  use scale_info::{form::PortableForm, PortableRegistry, TypeDef};
+
+  fn pallet_calls(
+    names: &mut BTreeMap<[u8; 2], String>,
+    types: &PortableRegistry,
+    pallet: &str,
+    pallet_idx: u8,
+    calls_ty: u32,
+  ) {
+    if let Some(TypeDef::<PortableForm>::Variant(calls)) =
+      types.resolve(calls_ty).map(|ty| &ty.type_def)
+    {
+      for call in &calls.variants {
+        names.insert([pallet_idx, call.index], format!("{pallet}.{}", call.name));
+      }
+    }
+  }
+
+  let mut names = BTreeMap::new();
+  match &metadata.1 {
+    #[cfg(feature = "v14")]
+    RuntimeMetadata::V14(md) => {
+      for pallet in &md.pallets {
+        if let Some(calls) = &pallet.calls {
+          pallet_calls(
+            &mut names,
+            &md.types,
+            &pallet.name,
+            pallet.index,
+            calls.ty.id,
+          );
+        }
+      }
+    }
+    #[cfg(feature = "v15")]
+    RuntimeMetadata::V15(md) => {
+      for pallet in &md.pallets {
+        if let Some(calls) = &pallet.calls {
+          pallet_calls(
+            &mut names,
+            &md.types,
+            &pallet.name,
+            pallet.index,
+            calls.ty.id,
+          );
+        }
+      }
+    }
+    _ => (),
+  }
+  names
+}
+
+/// The runtime data cached by the client.  Reloaded after a runtime upgrade.
+struct RuntimeState {
+  version: RuntimeVersion,
+  #[cfg(feature = "type_info")]
+  metadata: Arc<RuntimeMetadataPrefixed>,
+  /// The `Pallet.call_name` of the runtime calls, keyed by pallet and call index.
   #[cfg(feature = "type_info")]
-  metadata: RuntimeMetadataPrefixed,
+  calls: BTreeMap<[u8; 2], String>,
+  #[cfg(feature = "metadata_hash")]
+  metadata_hash: Option<H256>,
+}
+
+impl RuntimeState {
+  #[cfg_attr(
+    not(any(feature = "type_info", feature = "metadata_hash")),
+    allow(unused_variables)
+  )]
+  async fn load(rpc: &RpcPool, version: RuntimeVersion) -> Result<Self> {
+    #[cfg(any(feature = "type_info", feature = "metadata_hash"))]
+    let metadata = InnerClient::rpc_get_metadata_bytes(rpc, None).await?;
+    #[cfg(feature = "metadata_hash")]
+    let metadata_hash = InnerClient::rpc_get_metadata_hash(rpc, &metadata).await;
+    #[cfg(feature = "type_info")]
+    let metadata = RuntimeMetadataPrefixed::decode(&mut metadata.as_slice())?;
+    Ok(Self {
+      version,
+      #[cfg(feature = "type_info")]
+      calls: metadata_call_names(&metadata),
+      #[cfg(feature = "type_info")]
+      metadata: Arc::new(metadata),
+      #[cfg(feature = "metadata_hash")]
+      metadata_hash,
+    })
+  }
+}
+
+struct InnerClient {
+  rpc: RpcPool,
+  runtime: RwLock<Arc<RuntimeState>>,
   genesis_hash: BlockHash,
+  /// Stops the runtime upgrade watcher when the client is dropped.
+  #[cfg(not(target_arch = "wasm32"))]
+  _stop_watcher: Option<oneshot::Sender<()>>,
 }
 
 impl InnerClient {
-  async fn new(url: &str) -> Result<Self> {
-    let rpc = RpcClient::new(url).await?;
+  async fn new(rpc: RpcPool) -> Result<Self> {
     let runtime_version = Self::rpc_get_runtime_version(&rpc, None)
       .await?
-      .ok_or_else(|| Error::RpcClient(format!("Failed to get RuntimeVersion")))?;
-    #[cfg(feature = "type_info")]
-    let metadata = Self::rpc_get_metadata(&rpc, None)
-      .await?
-      .ok_or_else(|| Error::RpcClient(format!("Failed to get chain metadata")))?;
+      .ok_or_else(|| Error::RpcClient("Failed to get RuntimeVersion".into()))?;
+    let runtime = RuntimeState::load(&rpc, runtime_version).await?;
     let genesis_hash = Self::rpc_get_block_hash(&rpc, 0)
       .await?
-      .ok_or_else(|| Error::RpcClient(format!("Failed to get chain Genesis hash")))?;
+      .ok_or_else(|| Error::RpcClient("Failed to get chain Genesis hash".into()))?;
     Ok(Self {
       rpc,
-      runtime_version,
-      #[cfg(feature = "type_info")]
-      metadata,
+      runtime: RwLock::new(Arc::new(runtime)),
       genesis_hash,
+      #[cfg(not(target_arch = "wasm32"))]
+      _stop_watcher: None,
     })
   }
 
+  fn runtime(&self) -> Arc<RuntimeState> {
+    self
+      .runtime
+      .read()
+      .expect("Client runtime lock poisoned")
+      .clone()
+  }
+
+  /// Reload the cached metadata if the runtime was upgraded.
+  async fn update_runtime(&self, version: RuntimeVersion) -> Result<()> {
+    let current = self.runtime();
+    if current.version.spec_version == version.spec_version
+      && current.version.transaction_version == version.transaction_version
+    {
+      return Ok(());
+    }
+    log::info!(
+      "Runtime upgraded from spec {} to {}, reloading metadata",
+      current.version.spec_version,
+      version.spec_version
+    );
+    let runtime = RuntimeState::load(&self.rpc, version).await?;
+    *self.runtime.write().expect("Client runtime lock poisoned") = Arc::new(runtime);
+    Ok(())
+  }
+
+  #[cfg(feature = "type_info")]
+  fn check_call(&self, name: &str, call: &[u8]) -> Result<()> {
+    let runtime = self.runtime();
+    // Old metadata formats don't have the call indices.
+    if runtime.calls.is_empty() {
+      return Ok(());
+    }
+    let spec = runtime.version.spec_version;
+    let idx = match call {
+      [pallet, call, ..] => [*pallet, *call],
+      _ => {
+        return Err(Error::IncompatibleCall(format!(
+          "`{name}` is not a valid encoded call"
+        )))
+      }
+    };
+    match runtime.calls.get(&idx) {
+      Some(current) if current == name => Ok(()),
+      Some(current) => Err(Error::IncompatibleCall(format!(
+        "`{name}` has call index {idx:?}, which is `{current}` in runtime spec {spec}"
+      ))),
+      None => Err(Error::IncompatibleCall(format!(
+        "`{name}` has call index {idx:?}, which doesn't exist in runtime spec {spec}"
+      ))),
+    }
+  }
+
+  /// Calculate the RFC-78 metadata hash of the runtime `metadata`, if the runtime
+  /// has the `CheckMetadataHash` signed extension.
+  ///
+  /// Returns `None` if the hash can't be calculated (e.g. the chain's `system_properties`
+  /// don't have a single token), then transactions are signed without the metadata hash.
+  #[cfg(feature = "metadata_hash")]
+  async fn rpc_get_metadata_hash(rpc: &RpcPool, metadata: &[u8]) -> Option<H256> {
+    let res = async {
+      if !has_check_metadata_hash(metadata)? {
+        return Ok(None);
+      }
+      let properties: SystemProperties = rpc.request("system_properties", rpc_params!()).await?;
+      let decimals = u8::try_from(properties.token_decimals)
+        .map_err(|_| Error::MetadataHash("Invalid token decimals".into()))?;
+      calculate_metadata_hash(metadata, decimals, &properties.token_symbol).map(Some)
+    };
+    match res.await {
+      Ok(hash) => hash,
+      Err(err) => {
+        log::warn!("Failed to calculate the metadata hash, signing without it: {err:?}");
+        None
+      }
+    }
+  }
+
   fn get_transaction_version(&self) -> i64 {
-    self.runtime_version.transaction_version as i64
+    self.runtime().version.transaction_version as i64
+  }
+
+  fn get_runtime_version(&self) -> RuntimeVersion {
+    self.runtime().version.clone()
   }
 
   #[cfg(feature = "type_info")]
-  fn get_metadata(&self) -> &RuntimeMetadataPrefixed {
-    &self.metadata
+  fn get_metadata(&self) -> Arc<RuntimeMetadataPrefixed> {
+    self.runtime().metadata.clone()
   }
 
   fn get_genesis_hash(&self) -> BlockHash {
     self.genesis_hash
   }
 
-  async fn get_additional_signed(&self, lifetime: Option<u64>) -> Result<(AdditionalSigned, Era)> {
+  #[cfg(feature = "metadata_hash")]
+  fn get_metadata_hash(&self) -> Option<H256> {
+    self.runtime().metadata_hash
+  }
+
+  async fn get_additional_signed(
+    &self,
+    lifetime: Option<u64>,
+    at: Option<BlockHash>,
+  ) -> Result<(AdditionalSigned, Era)> {
+    let runtime = self.runtime();
     let mut addititional = AdditionalSigned {
-      spec_version: self.runtime_version.spec_version,
-      tx_version: self.runtime_version.transaction_version,
+      spec_version: runtime.version.spec_version,
+      tx_version: runtime.version.transaction_version,
       genesis_hash: self.genesis_hash,
       current_hash: self.genesis_hash,
+      #[cfg(feature = "metadata_hash")]
+      metadata_hash: runtime.metadata_hash,
+      #[cfg(not(feature = "metadata_hash"))]
       metadata_hash: None,
     };
     let era = match lifetime {
       Some(0) => Era::immortal(),
       lifetime => {
         let current = self
-          .get_block_header(None)
+          .get_block_header(at)
           .await?
           .ok_or_else(|| Error::RpcClient("Failed to get current block".into()))?;
         let number = current.number;
-        // Need to use the current block hash.
+        // Need to use the current block hash (or block `at`).
         addititional.current_hash = current.hash();
         Era::mortal(number, lifetime)
       }
@@ -127,6 +338,24 @@ impl InnerClient {
     self.rpc.request(method, params).await
   }
 
+  /// Make a request for the state at block `at`.  Requests for a specific block
+  /// can be sent to any endpoint, requests for the latest state use the active endpoint.
+  #[cfg(feature = "serde")]
+  async fn request_at<R>(
+    &self,
+    method: &str,
+    params: ArrayParams,
+    at: Option<BlockHash>,
+  ) -> Result<R>
+  where
+    R: DeserializeOwned,
+  {
+    match at {
+      Some(_) => self.rpc.read_request(method, params).await,
+      None => self.rpc.request(method, params).await,
+    }
+  }
+
   #[cfg(feature = "serde")]
   async fn batch_request<'a, R>(
     &self,
@@ -146,7 +375,7 @@ impl InnerClient {
     unsubscribe_method: &'a str,
   ) -> Result<Subscription<Notif>>
   where
-    Notif: DeserializeOwned,
+    Notif: DeserializeOwned + Send + 'static,
   {
     self
       .rpc
@@ -154,14 +383,16 @@ impl InnerClient {
       .await
   }
 
-  async fn rpc_get_block_hash(rpc: &RpcClient, block_number: u32) -> Result<Option<BlockHash>> {
+  async fn rpc_get_block_hash(rpc: &RpcPool, block_number: u32) -> Result<Option<BlockHash>> {
     let params = rpc_params!(block_number);
     Ok(rpc.request("chain_getBlockHash", params).await?)
   }
 
   /// Get the header of a block.
   async fn get_block_header(&self, block: Option<BlockHash>) -> Result<Option<Header>> {
-    Ok(self.request("chain_getHeader", rpc_params!(block)).await?)
+    self
+      .request_at("chain_getHeader", rpc_params!(block), block)
+      .await
   }
 
   /// Get the block hash for a `block_number`.
@@ -170,7 +401,7 @@ impl InnerClient {
   }
 
   async fn rpc_get_runtime_version(
-    rpc: &RpcClient,
+    rpc: &RpcPool,
     block: Option<BlockHash>,
   ) -> Result<Option<RuntimeVersion>> {
     let params = rpc_params!(block);
@@ -185,16 +416,45 @@ impl InnerClient {
     Self::rpc_get_runtime_version(&self.rpc, block).await
   }
 
+  /// Get the encoded V15 metadata with the `Metadata_metadata_at_version` runtime API.
+  ///
+  /// Returns `None` if the runtime doesn't support V15 metadata.
+  #[cfg(any(all(feature = "v15", feature = "type_info"), feature = "metadata_hash"))]
+  async fn rpc_get_metadata_v15(rpc: &RpcPool, block: Option<BlockHash>) -> Option<Vec<u8>> {
+    use codec::Encode;
+    let res: sp_core::Bytes = rpc
+      .request(
+        "state_call",
+        rpc_params!(
+          "Metadata_metadata_at_version",
+          sp_core::Bytes(15u32.encode()),
+          block
+        ),
+      )
+      .await
+      .ok()?;
+    Option::<Vec<u8>>::decode(&mut &res[..]).ok().flatten()
+  }
+
+  /// Get the SCALE encoded metadata of a block.
+  #[cfg(any(feature = "type_info", feature = "metadata_hash"))]
+  async fn rpc_get_metadata_bytes(rpc: &RpcPool, block: Option<BlockHash>) -> Result<Vec<u8>> {
+    // Prefer the V15 metadata, it has the runtime APIs.
+    #[cfg(any(all(feature = "v15", feature = "type_info"), feature = "metadata_hash"))]
+    if let Some(bytes) = Self::rpc_get_metadata_v15(rpc, block).await {
+      return Ok(bytes);
+    }
+    let params = rpc_params!(block);
+    let metadata: sp_core::Bytes = rpc.request("state_getMetadata", params).await?;
+    Ok(metadata.0)
+  }
+
   #[cfg(feature = "type_info")]
   async fn rpc_get_metadata(
-    rpc: &RpcClient,
+    rpc: &RpcPool,
     block: Option<BlockHash>,
   ) -> Result<Option<RuntimeMetadataPrefixed>> {
-    use hex::FromHex;
-    let params = rpc_params!(block);
-    let hex: String = rpc.request("state_getMetadata", params).await?;
-
-    let bytes = Vec::from_hex(&hex[2..])?;
+    let bytes = Self::rpc_get_metadata_bytes(rpc, block).await?;
     Ok(Some(RuntimeMetadataPrefixed::decode(
       &mut bytes.as_slice(),
     )?))
@@ -217,29 +477,209 @@ pub struct Client {
 
 impl Client {
   pub async fn new(url: &str) -> Result<Self> {
-    Ok(Self {
-      inner: Arc::new(InnerClient::new(url).await?),
-    })
+    let rpc = RpcClient::new(url).await?;
+    Self::from_rpc(RpcPool::single(rpc)).await
+  }
+
+  /// Create a client that uses a custom RPC transport (e.g. [`MockNode`](crate::mock::MockNode)).
+  pub async fn new_with_transport(url: &str, transport: impl RpcTransport) -> Result<Self> {
+    let rpc = RpcClient::new_with_transport(url, transport);
+    Self::from_rpc(RpcPool::single(rpc)).await
+  }
+
+  /// Connect to a node and automatically reconnect if the websocket connection is lost.
+  ///
+  /// See [`RpcClient::new_with_reconnect`].
+  #[cfg(not(target_arch = "wasm32"))]
+  pub async fn new_with_reconnect(url: &str, reconnect: ReconnectConfig) -> Result<Self> {
+    let rpc = RpcClient::new_with_reconnect(url, reconnect).await?;
+    Self::from_rpc(RpcPool::single(rpc)).await
+  }
+
+  /// Connect to multiple nodes of the same chain.
+  ///
+  /// Requests fail over to the next healthy node when a node stops responding.
+  /// See [`EndpointsConfig`] for load balancing of read-only queries.
+  pub async fn new_with_endpoints(urls: &[&str], config: EndpointsConfig) -> Result<Self> {
+    let rpc = RpcPool::connect(urls, config).await?;
+    Self::from_rpc(rpc).await
+  }
+
+  async fn from_rpc(rpc: RpcPool) -> Result<Self> {
+    #[cfg_attr(target_arch = "wasm32", allow(unused_mut))]
+    let mut inner = InnerClient::new(rpc).await?;
+    // Watch for runtime upgrades, when running inside a tokio runtime.
+    #[cfg(not(target_arch = "wasm32"))]
+    let watcher = tokio::runtime::Handle::try_current().ok().map(|handle| {
+      let (stop, stopped) = oneshot::channel();
+      inner._stop_watcher = Some(stop);
+      (handle, stopped)
+    });
+    let client = Self {
+      inner: Arc::new(inner),
+    };
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some((handle, stopped)) = watcher {
+      handle.spawn(Self::watch_runtime(Arc::downgrade(&client.inner), stopped));
+    }
+    Ok(client)
+  }
+
+  /// Watch for runtime upgrades and reload the cached runtime version and metadata.
+  ///
+  /// The subscription is re-issued with a backoff when it ends.  Stops when the client
+  /// is dropped or if the node doesn't support subscriptions.
+  #[cfg(not(target_arch = "wasm32"))]
+  async fn watch_runtime(inner: Weak<InnerClient>, mut stopped: oneshot::Receiver<()>) {
+    let backoff = ReconnectConfig::default();
+    let mut attempt = 0;
+    loop {
+      // Only hold the client while subscribing, so it can be dropped.
+      let res = match inner.upgrade() {
+        Some(inner) => {
+          inner
+            .rpc
+            .subscribe::<RuntimeVersion>(
+              "chain_subscribeRuntimeVersion",
+              rpc_params!(),
+              "chain_unsubscribeRuntimeVersion",
+            )
+            .await
+        }
+        None => return,
+      };
+      match res {
+        Ok(mut sub) => loop {
+          let res = tokio::select! {
+            _ = &mut stopped => return,
+            res = sub.next() => res,
+          };
+          match res {
+            Some(Ok(version)) => {
+              attempt = 0;
+              let inner = match inner.upgrade() {
+                Some(inner) => inner,
+                None => return,
+              };
+              if let Err(err) = inner.update_runtime(version).await {
+                log::warn!("Failed to reload the runtime metadata: {err:?}");
+              }
+            }
+            Some(Err(err)) => {
+              log::warn!("Runtime version subscription failed: {err:?}");
+              break;
+            }
+            None => {
+              log::debug!("Runtime version subscription ended");
+              break;
+            }
+          }
+        },
+        Err(err) if !is_node_error(&err) => {
+          log::debug!("Can't watch for runtime upgrades: {err:?}");
+          return;
+        }
+        Err(err) => {
+          log::warn!("Failed to subscribe to runtime upgrades: {err:?}");
+        }
+      }
+      let delay = backoff.delay(attempt);
+      attempt += 1;
+      tokio::select! {
+        _ = &mut stopped => return,
+        _ = tokio::time::sleep(delay) => (),
+      }
+    }
+  }
+
+  /// The RPC endpoints this client is connected to.
+  pub fn endpoints(&self) -> &RpcPool {
+    &self.inner.rpc
   }
 
   pub fn get_transaction_version(&self) -> i64 {
     self.inner.get_transaction_version()
   }
 
+  /// The runtime version of the best block.  Updated after a runtime upgrade.
+  pub fn get_runtime_version(&self) -> RuntimeVersion {
+    self.inner.get_runtime_version()
+  }
+
+  /// The chain metadata of the current runtime.  Reloaded after a runtime upgrade.
   #[cfg(feature = "type_info")]
-  pub fn get_metadata(&self) -> &RuntimeMetadataPrefixed {
+  pub fn get_metadata(&self) -> Arc<RuntimeMetadataPrefixed> {
     self.inner.get_metadata()
   }
 
+  /// Compare the type hashes of a generated API with the metadata of the current runtime.
+  #[cfg(feature = "type_info")]
+  pub fn check_compatibility(&self, api: &ApiHashes) -> Result<CompatibilityReport> {
+    let runtime = self.inner.runtime();
+    let chain = MetadataHashes::new(&runtime.metadata)?;
+    Ok(CompatibilityReport::new(
+      runtime.version.spec_version,
+      api,
+      &chain,
+    ))
+  }
+
+  /// Check that the encoded `call` is the call `name` (`Pallet.call_name`) in the current runtime.
+  ///
+  /// The pallet and call indices can change in a runtime upgrade, then the call would
+  /// execute a different call than the one compiled into the API.
+  pub fn check_call(&self, name: &str, call: &[u8]) -> Result<()> {
+    #[cfg(feature = "type_info")]
+    return self.inner.check_call(name, call);
+    #[cfg(not(feature = "type_info"))]
+    {
+      let _ = (name, call);
+      Ok(())
+    }
+  }
+
   pub fn get_genesis_hash(&self) -> BlockHash {
     self.inner.get_genesis_hash()
   }
 
+  /// The RFC-78 metadata hash of the runtime, used by the `CheckMetadataHash` signed extension.
+  ///
+  /// Transactions are signed with the metadata hash, when the chain supports it.
+  #[cfg(feature = "metadata_hash")]
+  pub fn get_metadata_hash(&self) -> Option<H256> {
+    self.inner.get_metadata_hash()
+  }
+
   pub async fn get_additional_signed(
     &self,
     lifetime: Option<u64>,
   ) -> Result<(AdditionalSigned, Era)> {
-    self.inner.get_additional_signed(lifetime).await
+    self.inner.get_additional_signed(lifetime, None).await
+  }
+
+  /// Same as [`Self::get_additional_signed`], but the mortal era starts at block `at`
+  /// instead of the best block.
+  pub async fn get_additional_signed_at(
+    &self,
+    lifetime: Option<u64>,
+    at: BlockHash,
+  ) -> Result<(AdditionalSigned, Era)> {
+    self.inner.get_additional_signed(lifetime, Some(at)).await
+  }
+
+  /// Get the account's nonce from the `System.Account` storage at block `at`.
+  pub async fn get_account_nonce_at(&self, account: &AccountId, at: BlockHash) -> Result<u32> {
+    use codec::Encode;
+    use sp_core::hashing::{blake2_128, twox_128};
+    // Storage key: `twox128("System") ++ twox128("Account") ++ blake2_128_concat(account)`.
+    let mut key = twox_128(b"System").to_vec();
+    key.extend_from_slice(&twox_128(b"Account"));
+    let account = account.encode();
+    key.extend_from_slice(&blake2_128(&account));
+    key.extend_from_slice(&account);
+    // The `AccountInfo` starts with the nonce.
+    let nonce = self.get_storage_by_key(StorageKey(key), Some(at)).await?;
+    Ok(nonce.unwrap_or_default())
   }
 
   /// Get the `SystemProperties` of the chain.
@@ -247,6 +687,11 @@ impl Client {
     self.request("system_properties", rpc_params!()).await
   }
 
+  /// Get the `SystemHealth` of the active node.
+  pub async fn get_system_health(&self) -> Result<SystemHealth> {
+    self.request("system_health", rpc_params!()).await
+  }
+
   pub async fn get_storage_keys_paged(
     &self,
     prefix: &StorageKey,
@@ -256,10 +701,108 @@ impl Client {
   ) -> Result<Vec<StorageKey>> {
     let params = rpc_params!(prefix, count, start_key.unwrap_or(prefix), at);
     self
-      .request::<Vec<StorageKey>>("state_getKeysPaged", params)
+      .inner
+      .request_at::<Vec<StorageKey>>("state_getKeysPaged", params, at)
       .await
   }
 
+  /// Get all storage keys with `prefix` at block `at`.
+  pub async fn get_all_storage_keys(
+    &self,
+    prefix: &StorageKey,
+    at: Option<BlockHash>,
+  ) -> Result<Vec<StorageKey>> {
+    let mut keys = Vec::new();
+    loop {
+      let page = self
+        .get_storage_keys_paged(prefix, STORAGE_KEYS_PAGE_SIZE, keys.last(), at)
+        .await?;
+      let finished = page.len() < STORAGE_KEYS_PAGE_SIZE;
+      keys.extend(page);
+      if finished {
+        return Ok(keys);
+      }
+    }
+  }
+
+  /// Get the values of multiple storage `keys` at block `at`.
+  ///
+  /// The values are requested in batches with `state_queryStorageAt`.
+  pub async fn get_storage_data_by_keys(
+    &self,
+    keys: &[StorageKey],
+    at: Option<BlockHash>,
+  ) -> Result<Vec<Option<StorageData>>> {
+    let mut values = BTreeMap::new();
+    for batch in keys.chunks(STORAGE_VALUES_BATCH_SIZE) {
+      let sets: Vec<StorageChangeSet> = self
+        .inner
+        .request_at("state_queryStorageAt", rpc_params!(batch, at), at)
+        .await?;
+      for (key, value) in sets.into_iter().flat_map(|set| set.changes) {
+        values.insert(key.0, value);
+      }
+    }
+    Ok(
+      keys
+        .iter()
+        .map(|key| values.get(&key.0).cloned().flatten())
+        .collect(),
+    )
+  }
+
+  /// Compare the storage map entries with `prefix` at `from_block` and `to_block`.
+  ///
+  /// `key_hash_len` is the length of the hash before the last key (see [`StoragePaged`]).
+  pub async fn storage_diff<K: Decode, V: Decode>(
+    &self,
+    prefix: &StorageKey,
+    key_hash_len: Option<usize>,
+    from_block: BlockHash,
+    to_block: BlockHash,
+  ) -> Result<StorageDiff<K, V>> {
+    let old = self.get_storage_entries(prefix, from_block).await?;
+    let mut new = self.get_storage_entries(prefix, to_block).await?;
+
+    let mut diff = StorageDiff::default();
+    let decode = |key: &[u8], value: StorageData| -> Result<(K, V)> {
+      let key = decode_storage_key(prefix, key_hash_len, &StorageKey(key.to_vec()))?;
+      Ok((key, V::decode(&mut value.0.as_slice())?))
+    };
+    for (key, old_value) in old {
+      match new.remove(&key) {
+        Some(new_value) if new_value.0 == old_value.0 => (),
+        Some(new_value) => {
+          let (key, old) = decode(&key, old_value)?;
+          let new = V::decode(&mut new_value.0.as_slice())?;
+          diff.changed.push(ChangedEntry { key, old, new });
+        }
+        None => diff.removed.push(decode(&key, old_value)?),
+      }
+    }
+    for (key, new_value) in new {
+      diff.added.push(decode(&key, new_value)?);
+    }
+    Ok(diff)
+  }
+
+  /// Get all storage entries with `prefix` at block `at`.
+  async fn get_storage_entries(
+    &self,
+    prefix: &StorageKey,
+    at: BlockHash,
+  ) -> Result<BTreeMap<Vec<u8>, StorageData>> {
+    let keys = self.get_all_storage_keys(prefix, Some(at)).await?;
+    let values = self.get_storage_data_by_keys(&keys, Some(at)).await?;
+    Ok(
+      keys
+        .into_iter()
+        .zip(values)
+        .filter_map(|(key, value)| value.map(|value| (key.0, value)))
+        .collect(),
+    )
+  }
+
   pub async fn get_storage_by_key<T: Decode>(
     &self,
     key: StorageKey,
@@ -280,11 +823,68 @@ impl Client {
   ) -> Result<Option<StorageData>> {
     Ok(
       self
-        .request("state_getStorage", rpc_params!(key, at))
+        .inner
+        .request_at("state_getStorage", rpc_params!(key, at), at)
         .await?,
     )
   }
 
+  /// Get a read proof for the storage `keys` at block `at`.
+  pub async fn get_read_proof(
+    &self,
+    keys: &[StorageKey],
+    at: Option<BlockHash>,
+  ) -> Result<ReadProof> {
+    self
+      .inner
+      .request_at("state_getReadProof", rpc_params!(keys, at), at)
+      .await
+  }
+
+  /// Get a storage value and verify it with a read proof.
+  ///
+  /// The proof is checked against the `state_root` of the block header.  The header
+  /// is checked against the block hash `at`, so only the block hash needs to be trusted.
+  /// If `at` is `None` the latest block header from the node is used.
+  pub async fn get_verified_storage_by_key<T: Decode>(
+    &self,
+    key: StorageKey,
+    at: Option<BlockHash>,
+  ) -> Result<Option<T>> {
+    let value = self
+      .get_verified_storage_data_by_key(key, at)
+      .await?
+      .map(|data| T::decode(&mut data.0.as_slice()))
+      .transpose()?;
+    Ok(value)
+  }
+
+  /// Get raw storage data and verify it with a read proof.
+  ///
+  /// See [`Self::get_verified_storage_by_key`].
+  pub async fn get_verified_storage_data_by_key(
+    &self,
+    key: StorageKey,
+    at: Option<BlockHash>,
+  ) -> Result<Option<StorageData>> {
+    let header = self
+      .get_block_header(at)
+      .await?
+      .ok_or_else(|| Error::StorageProofFailed("Missing block header".into()))?;
+    let hash = header.hash();
+    if let Some(at) = at {
+      if hash != at {
+        return Err(Error::StorageProofFailed(format!(
+          "Block header hash mismatch: expected {at:?}, got {hash:?}"
+        )));
+      }
+    }
+    let proof = self
+      .get_read_proof(core::slice::from_ref(&key), Some(hash))
+      .await?;
+    proof.verify(&header.state_root, &key)
+  }
+
   /// Subscribe to new blocks.
   pub async fn subscribe_blocks(&self) -> Result<Subscription<Header>> {
     Ok(
@@ -311,6 +911,32 @@ impl Client {
     )
   }
 
+  /// Subscribe to changes of the storage `keys`.
+  ///
+  /// The first notification has the current values of the keys.
+  pub async fn subscribe_storage(
+    &self,
+    keys: Vec<StorageKey>,
+  ) -> Result<Subscription<StorageChangeSet>> {
+    self
+      .subscribe(
+        "state_subscribeStorage",
+        rpc_params!(keys),
+        "state_unsubscribeStorage",
+      )
+      .await
+  }
+
+  /// Subscribe to changes of the storage `key` and decode the new values with `decode`.
+  pub async fn subscribe_storage_by_key<T>(
+    &self,
+    key: StorageKey,
+    decode: fn(Option<StorageData>) -> Result<T>,
+  ) -> Result<StorageSubscription<T>> {
+    let sub = self.subscribe_storage(vec![key.clone()]).await?;
+    Ok(StorageSubscription::new(sub, key, decode))
+  }
+
   /// Submit and watch a transaction.
   pub async fn submit_and_watch(&self, tx_hex: String) -> Result<Subscription<TransactionStatus>> {
     Ok(
@@ -324,6 +950,59 @@ impl Client {
     )
   }
 
+  /// Dry-run a signed transaction at block `at`.
+  ///
+  /// Returns the SCALE encoded `ApplyExtrinsicResult`.
+  pub async fn dry_run(&self, tx_hex: String, at: Option<BlockHash>) -> Result<Vec<u8>> {
+    let result: sp_core::Bytes = self
+      .inner
+      .request_at("system_dryRun", rpc_params!(tx_hex, at), at)
+      .await?;
+    Ok(result.0)
+  }
+
+  /// Call the runtime API `method` (e.g. `TransactionPaymentApi_query_info`) with the
+  /// SCALE encoded `args` at block `at`.
+  ///
+  /// Returns the SCALE encoded result.
+  pub async fn state_call(
+    &self,
+    method: &str,
+    args: &[u8],
+    at: Option<BlockHash>,
+  ) -> Result<Vec<u8>> {
+    let args = sp_core::Bytes(args.to_vec());
+    let result: sp_core::Bytes = self
+      .inner
+      .request_at("state_call", rpc_params!(method, args, at), at)
+      .await?;
+    Ok(result.0)
+  }
+
+  /// Query the weight, class and fee of a signed transaction.
+  pub async fn query_fee_info(
+    &self,
+    tx_hex: String,
+    at: Option<BlockHash>,
+  ) -> Result<RuntimeDispatchInfo> {
+    self
+      .inner
+      .request_at("payment_queryInfo", rpc_params!(tx_hex, at), at)
+      .await
+  }
+
+  /// Query the fee breakdown of a signed transaction.
+  pub async fn query_fee_details(
+    &self,
+    tx_hex: String,
+    at: Option<BlockHash>,
+  ) -> Result<FeeDetails> {
+    self
+      .inner
+      .request_at("payment_queryFeeDetails", rpc_params!(tx_hex, at), at)
+      .await
+  }
+
   /// Make a RPC request to the node.
   #[cfg(feature = "serde")]
   pub async fn request<'a, R>(&self, method: &'a str, params: ArrayParams) -> Result<R>
@@ -354,7 +1033,7 @@ impl Client {
     unsubscribe_method: &'a str,
   ) -> Result<Subscription<Notif>>
   where
-    Notif: DeserializeOwned,
+    Notif: DeserializeOwned + Send + 'static,
   {
     self
       .inner
@@ -373,7 +1052,10 @@ impl Client {
 
   /// Get a block.
   pub async fn get_signed_block(&self, block: Option<BlockHash>) -> Result<Option<SignedBlock>> {
-    Ok(self.request("chain_getBlock", rpc_params!(block)).await?)
+    self
+      .inner
+      .request_at("chain_getBlock", rpc_params!(block), block)
+      .await
   }
 
   /// Get a block.
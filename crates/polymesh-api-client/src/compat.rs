@@ -0,0 +1,407 @@
+#[cfg(all(not(feature = "std"), feature = "type_info"))]
+use alloc::collections::btree_set::BTreeSet;
+#[cfg(not(feature = "std"))]
+use alloc::{collections::btree_map::BTreeMap, format, string::String};
+#[cfg(feature = "std")]
+use std::collections::BTreeMap;
+#[cfg(all(feature = "std", feature = "type_info"))]
+use std::collections::BTreeSet;
+
+use sp_std::prelude::*;
+
+#[cfg(feature = "type_info")]
+use codec::Encode;
+#[cfg(feature = "type_info")]
+use frame_metadata::{RuntimeMetadata, RuntimeMetadataPrefixed};
+#[cfg(feature = "type_info")]
+use scale_info::{form::PortableForm, Field, PortableRegistry, TypeDef, Variant};
+
+#[cfg(feature = "type_info")]
+use crate::*;
+
+/// Hash of the SCALE layout of a type.
+///
+/// Type paths and docs are not hashed, so moving a type to another crate doesn't
+/// change its hash.  Field and variant names are hashed.
+///
+/// The codegen (`polymesh-api-codegen`) uses [`TypeHasher`] for the hashes
+/// embedded in the generated API.
+pub type TypeHash = [u8; 32];
+
+/// The type hashes of the calls, events and storage entries of a generated API.
+///
+/// Calls and events are named `Pallet.name`, storage entries are named `Prefix.Name`.
+#[derive(Clone, Copy, Debug)]
+pub struct ApiHashes {
+  pub calls: &'static [(&'static str, TypeHash)],
+  pub events: &'static [(&'static str, TypeHash)],
+  pub storage: &'static [(&'static str, TypeHash)],
+}
+
+/// Why a call, event or storage entry is incompatible with the chain.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IncompatibleReason {
+  /// Not in the chain metadata.
+  Missing,
+  /// The indices or types are different in the chain metadata.
+  Changed,
+}
+
+/// A call, event or storage entry of the API that is incompatible with the chain.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Incompatible {
+  pub name: String,
+  pub reason: IncompatibleReason,
+}
+
+/// The result of comparing a generated API with the chain metadata.
+#[derive(Clone, Debug, Default)]
+pub struct CompatibilityReport {
+  /// The spec version of the chain runtime.
+  pub spec_version: u32,
+  pub calls: Vec<Incompatible>,
+  pub events: Vec<Incompatible>,
+  pub storage: Vec<Incompatible>,
+}
+
+impl CompatibilityReport {
+  /// Compare the type hashes of the API with the hashes of the chain metadata.
+  pub fn new(spec_version: u32, api: &ApiHashes, chain: &MetadataHashes) -> Self {
+    fn compare(api: &[(&str, TypeHash)], chain: &BTreeMap<String, TypeHash>) -> Vec<Incompatible> {
+      api
+        .iter()
+        .filter_map(|(name, hash)| {
+          let reason = match chain.get(*name) {
+            None => IncompatibleReason::Missing,
+            Some(chain_hash) if chain_hash != hash => IncompatibleReason::Changed,
+            Some(_) => return None,
+          };
+          Some(Incompatible {
+            name: String::from(*name),
+            reason,
+          })
+        })
+        .collect()
+    }
+
+    Self {
+      spec_version,
+      calls: compare(api.calls, &chain.calls),
+      events: compare(api.events, &chain.events),
+      storage: compare(api.storage, &chain.storage),
+    }
+  }
+
+  /// No incompatible calls, events or storage entries.
+  pub fn is_compatible(&self) -> bool {
+    self.calls.is_empty() && self.events.is_empty() && self.storage.is_empty()
+  }
+}
+
+/// The type hashes of the calls, events and storage entries in the chain metadata.
+#[derive(Clone, Debug, Default)]
+pub struct MetadataHashes {
+  pub calls: BTreeMap<String, TypeHash>,
+  pub events: BTreeMap<String, TypeHash>,
+  pub storage: BTreeMap<String, TypeHash>,
+}
+
+#[cfg(feature = "type_info")]
+impl MetadataHashes {
+  /// Hash the calls, events and storage entries of V14 or V15 metadata.
+  pub fn new(metadata: &RuntimeMetadataPrefixed) -> Result<Self> {
+    let mut hashes = Self::default();
+    match &metadata.1 {
+      #[cfg(feature = "v14")]
+      RuntimeMetadata::V14(md) => {
+        let mut hasher = TypeHasher::new(&md.types);
+        for pallet in &md.pallets {
+          hashes.add_pallet(
+            &mut hasher,
+            &pallet.name,
+            pallet.index,
+            pallet.calls.as_ref().map(|calls| calls.ty.id),
+            pallet.event.as_ref().map(|event| event.ty.id),
+            pallet.storage.as_ref(),
+          );
+        }
+      }
+      #[cfg(feature = "v15")]
+      RuntimeMetadata::V15(md) => {
+        let mut hasher = TypeHasher::new(&md.types);
+        for pallet in &md.pallets {
+          hashes.add_pallet(
+            &mut hasher,
+            &pallet.name,
+            pallet.index,
+            pallet.calls.as_ref().map(|calls| calls.ty.id),
+            pallet.event.as_ref().map(|event| event.ty.id),
+            pallet.storage.as_ref(),
+          );
+        }
+      }
+      _ => {
+        return Err(Error::MetadataParseFailed(
+          "Unsupported metadata version".into(),
+        ));
+      }
+    }
+    Ok(hashes)
+  }
+
+  #[cfg(feature = "v14")]
+  fn add_pallet(
+    &mut self,
+    hasher: &mut TypeHasher,
+    pallet: &str,
+    pallet_idx: u8,
+    calls_ty: Option<u32>,
+    event_ty: Option<u32>,
+    storage: Option<&frame_metadata::v14::PalletStorageMetadata<PortableForm>>,
+  ) {
+    if let Some(id) = calls_ty {
+      for (name, hash) in hasher.variant_hashes(pallet_idx, id) {
+        self.calls.insert(format!("{pallet}.{name}"), hash);
+      }
+    }
+    if let Some(id) = event_ty {
+      for (name, hash) in hasher.variant_hashes(pallet_idx, id) {
+        self.events.insert(format!("{pallet}.{name}"), hash);
+      }
+    }
+    if let Some(storage) = storage {
+      for entry in &storage.entries {
+        self.storage.insert(
+          format!("{}.{}", storage.prefix, entry.name),
+          hasher.storage_hash(entry),
+        );
+      }
+    }
+  }
+}
+
+/// Hash used for a type that references itself.
+#[cfg(feature = "type_info")]
+const RECURSIVE_TYPE_HASH: TypeHash = [0u8; 32];
+
+/// Calculates [`TypeHash`]es of the types in a registry.
+///
+/// Also used by `polymesh-api-codegen` to embed the hashes in the generated API.
+#[cfg(feature = "type_info")]
+pub struct TypeHasher<'a> {
+  types: &'a PortableRegistry,
+  /// Hashes of types that don't reference a type that is being hashed.
+  cache: BTreeMap<u32, TypeHash>,
+  visiting: BTreeSet<u32>,
+}
+
+#[cfg(feature = "type_info")]
+impl<'a> TypeHasher<'a> {
+  pub fn new(types: &'a PortableRegistry) -> Self {
+    Self {
+      types,
+      cache: BTreeMap::new(),
+      visiting: BTreeSet::new(),
+    }
+  }
+
+  /// The hash of type `id`.
+  pub fn type_hash(&mut self, id: u32) -> TypeHash {
+    self.hash(id).0
+  }
+
+  /// The hashes of the variants (calls or events) of a pallet enum type.
+  pub fn variant_hashes(&mut self, pallet_idx: u8, id: u32) -> Vec<(String, TypeHash)> {
+    let types = self.types;
+    match types.resolve(id).map(|ty| &ty.type_def) {
+      Some(TypeDef::Variant(def)) => def
+        .variants
+        .iter()
+        .map(|variant| {
+          let mut buf = vec![pallet_idx];
+          self.push_variant(&mut buf, variant, &mut false);
+          (variant.name.clone(), sp_core::hashing::blake2_256(&buf))
+        })
+        .collect(),
+      _ => Vec::new(),
+    }
+  }
+
+  /// The hash of a storage entry: modifier, hashers, key and value types.
+  #[cfg(feature = "v14")]
+  pub fn storage_hash(
+    &mut self,
+    entry: &frame_metadata::v14::StorageEntryMetadata<PortableForm>,
+  ) -> TypeHash {
+    use frame_metadata::v14::StorageEntryType;
+    let mut buf = vec![entry.modifier.clone() as u8];
+    match &entry.ty {
+      StorageEntryType::Plain(value) => {
+        buf.push(0);
+        self.push_type(&mut buf, value.id, &mut false);
+      }
+      StorageEntryType::Map {
+        hashers,
+        key,
+        value,
+      } => {
+        buf.push(1);
+        hashers.encode_to(&mut buf);
+        self.push_type(&mut buf, key.id, &mut false);
+        self.push_type(&mut buf, value.id, &mut false);
+      }
+    }
+    sp_core::hashing::blake2_256(&buf)
+  }
+
+  /// Returns the hash and if it depends on a type that is being hashed.
+  fn hash(&mut self, id: u32) -> (TypeHash, bool) {
+    if let Some(hash) = self.cache.get(&id) {
+      return (*hash, false);
+    }
+    if !self.visiting.insert(id) {
+      return (RECURSIVE_TYPE_HASH, true);
+    }
+    let types = self.types;
+    let mut recursive = false;
+    let mut buf = Vec::new();
+    match types.resolve(id).map(|ty| &ty.type_def) {
+      Some(TypeDef::Composite(def)) => {
+        buf.push(0);
+        self.push_fields(&mut buf, &def.fields, &mut recursive);
+      }
+      Some(TypeDef::Variant(def)) => {
+        buf.push(1);
+        (def.variants.len() as u32).encode_to(&mut buf);
+        for variant in &def.variants {
+          self.push_variant(&mut buf, variant, &mut recursive);
+        }
+      }
+      Some(TypeDef::Sequence(def)) => {
+        buf.push(2);
+        self.push_type(&mut buf, def.type_param.id, &mut recursive);
+      }
+      Some(TypeDef::Array(def)) => {
+        buf.push(3);
+        def.len.encode_to(&mut buf);
+        self.push_type(&mut buf, def.type_param.id, &mut recursive);
+      }
+      Some(TypeDef::Tuple(def)) => {
+        buf.push(4);
+        (def.fields.len() as u32).encode_to(&mut buf);
+        for field in &def.fields {
+          self.push_type(&mut buf, field.id, &mut recursive);
+        }
+      }
+      Some(TypeDef::Primitive(def)) => {
+        buf.push(5);
+        def.encode_to(&mut buf);
+      }
+      Some(TypeDef::Compact(def)) => {
+        buf.push(6);
+        self.push_type(&mut buf, def.type_param.id, &mut recursive);
+      }
+      Some(TypeDef::BitSequence(def)) => {
+        buf.push(7);
+        self.push_type(&mut buf, def.bit_store_type.id, &mut recursive);
+        // The bit order types have no fields, use their names.
+        let order = types
+          .resolve(def.bit_order_type.id)
+          .and_then(|ty| ty.path.segments.last());
+        order.encode_to(&mut buf);
+      }
+      None => {
+        buf.push(0xff);
+      }
+    }
+    self.visiting.remove(&id);
+    let hash = sp_core::hashing::blake2_256(&buf);
+    if !recursive {
+      self.cache.insert(id, hash);
+    }
+    (hash, recursive)
+  }
+
+  fn push_type(&mut self, buf: &mut Vec<u8>, id: u32, recursive: &mut bool) {
+    let (hash, is_recursive) = self.hash(id);
+    *recursive |= is_recursive;
+    buf.extend_from_slice(&hash);
+  }
+
+  fn push_fields(
+    &mut self,
+    buf: &mut Vec<u8>,
+    fields: &[Field<PortableForm>],
+    recursive: &mut bool,
+  ) {
+    (fields.len() as u32).encode_to(buf);
+    for field in fields {
+      field.name.encode_to(buf);
+      self.push_type(buf, field.ty.id, recursive);
+    }
+  }
+
+  fn push_variant(
+    &mut self,
+    buf: &mut Vec<u8>,
+    variant: &Variant<PortableForm>,
+    recursive: &mut bool,
+  ) {
+    buf.push(variant.index);
+    variant.name.encode_to(buf);
+    self.push_fields(buf, &variant.fields, recursive);
+  }
+}
+
+#[cfg(all(test, feature = "v14"))]
+mod tests {
+  use super::*;
+  use codec::Decode;
+
+  fn load_metadata(spec: &str) -> RuntimeMetadataPrefixed {
+    let path = format!(
+      "{}/../../specs/polymesh_dev_spec_{spec}.meta",
+      env!("CARGO_MANIFEST_DIR")
+    );
+    let data = std::fs::read(path).expect("spec file");
+    RuntimeMetadataPrefixed::decode(&mut data.as_slice()).expect("metadata")
+  }
+
+  #[test]
+  fn test_metadata_hashes() -> anyhow::Result<()> {
+    let v7 = MetadataHashes::new(&load_metadata("7004000"))?;
+    let v8 = MetadataHashes::new(&load_metadata("8000001"))?;
+    assert!(v7.calls.contains_key("Balances.transfer_with_memo"));
+    assert!(v7.events.contains_key("System.ExtrinsicSuccess"));
+    assert!(v7.storage.contains_key("System.Account"));
+    // Same call in both versions.
+    assert_eq!(v7.calls["System.remark"], v8.calls["System.remark"]);
+
+    let leak = |hashes: &BTreeMap<String, TypeHash>| -> &'static [(&'static str, TypeHash)] {
+      hashes
+        .iter()
+        .map(|(name, hash)| (&*name.clone().leak(), *hash))
+        .collect::<Vec<_>>()
+        .leak()
+    };
+    let api = ApiHashes {
+      calls: leak(&v7.calls),
+      events: leak(&v7.events),
+      storage: leak(&v7.storage),
+    };
+    assert!(CompatibilityReport::new(7004000, &api, &v7).is_compatible());
+    let report = CompatibilityReport::new(8000001, &api, &v8);
+    assert!(!report.is_compatible());
+    assert_eq!(report.spec_version, 8000001);
+    assert!(report.calls.contains(&Incompatible {
+      name: "Balances.transfer_with_memo".into(),
+      reason: IncompatibleReason::Changed,
+    }));
+    assert!(report.calls.contains(&Incompatible {
+      name: "Balances.burn_account_balance".into(),
+      reason: IncompatibleReason::Missing,
+    }));
+    assert!(!report.calls.iter().any(|c| c.name == "System.remark"));
+    Ok(())
+  }
+}
@@ -38,6 +38,9 @@ pub enum Error {
   )]
   IncompatibleCall(String),
 
+  #[cfg_attr(feature = "std", error("No API for runtime spec version: {0}"))]
+  UnsupportedSpecVersion(u32),
+
   #[cfg_attr(feature = "std", error("Schema failed to parse: {0}"))]
   SchemaParseFailed(String),
 
@@ -50,6 +53,9 @@ pub enum Error {
   #[cfg_attr(feature = "std", error("RpcClient: {0}"))]
   RpcClient(String),
 
+  #[cfg_attr(feature = "std", error("Connection lost: {0}"))]
+  ConnectionLost(String),
+
   #[cfg_attr(feature = "std", error("Decode type failed: {0}"))]
   DecodeTypeFailed(String),
 
@@ -59,9 +65,24 @@ pub enum Error {
   #[cfg_attr(feature = "std", error("Storage key generation failed: {0}"))]
   StorageKeyGenerationFailed(String),
 
+  #[cfg_attr(feature = "std", error("Storage proof verification failed: {0}"))]
+  StorageProofFailed(String),
+
   #[cfg_attr(feature = "std", error("Signing transaction failed: {0}"))]
   SigningTransactionFailed(String),
 
+  #[cfg_attr(feature = "std", error("Keystore error: {0}"))]
+  Keystore(String),
+
+  #[cfg_attr(feature = "std", error("Remote signer error: {0}"))]
+  RemoteSigner(String),
+
+  #[cfg_attr(feature = "std", error("Metadata hash error: {0}"))]
+  MetadataHash(String),
+
+  #[cfg_attr(feature = "std", error("Storage snapshot error: {0}"))]
+  StorageSnapshot(String),
+
   #[cfg_attr(feature = "std", error("Jsonrpsee error: {0}"))]
   Jsonrpsee(jsonrpsee::core::Error),
 
@@ -0,0 +1,183 @@
+use core::pin::Pin;
+use core::task::{Context, Poll};
+
+use async_stream::try_stream;
+use futures_core::stream::Stream;
+
+#[cfg(not(feature = "std"))]
+use alloc::{boxed::Box, format, string::String};
+use sp_std::prelude::*;
+
+use crate::*;
+
+/// An event emitted in a block.
+pub type BlockEvent<Event> = (BlockHash, BlockNumber, EventRecord<Event>);
+
+type EventPredicate<Event> = Box<dyn Fn(&Event) -> bool + Send + Sync>;
+
+/// Filter for events in an [`EventSubscription`].
+///
+/// The generated API has typed filters in its `event_filters` module
+/// (e.g. `event_filters::Settlement::InstructionExecuted`).  The `Pallet` and `Variant`
+/// filters match the event names and are a fallback for names only known at runtime.
+pub enum EventFilter<Event> {
+  /// Match events with a generated matcher.  The name is only used for `Debug`.
+  Typed(&'static str, fn(&Event) -> bool),
+  /// Match all events from a pallet by name.
+  Pallet(String),
+  /// Match one event variant from a pallet by name.
+  Variant(String, String),
+  /// Match events with a custom predicate.
+  Custom(EventPredicate<Event>),
+}
+
+impl<Event: RuntimeEnumTraits> EventFilter<Event> {
+  pub fn matches(&self, event: &Event) -> bool {
+    // Event names have the format `<Pallet>.<Variant>`.
+    let names = || {
+      let name = event.as_name();
+      name.split_once('.').unwrap_or((name, ""))
+    };
+    match self {
+      Self::Typed(_, f) => f(event),
+      Self::Pallet(p) => p == names().0,
+      Self::Variant(p, v) => (p.as_str(), v.as_str()) == names(),
+      Self::Custom(f) => f(event),
+    }
+  }
+}
+
+impl<Event> core::fmt::Debug for EventFilter<Event> {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    match self {
+      Self::Typed(name, _) => f.debug_tuple("Typed").field(name).finish(),
+      Self::Pallet(p) => f.debug_tuple("Pallet").field(p).finish(),
+      Self::Variant(p, v) => f.debug_tuple("Variant").field(p).field(v).finish(),
+      Self::Custom(_) => f.debug_tuple("Custom").finish_non_exhaustive(),
+    }
+  }
+}
+
+/// Stream of events from new blocks.
+///
+/// Without filters all events are returned.  With filters, only events matching
+/// at least one of the filters are returned.
+pub struct EventSubscription<Event> {
+  stream: Pin<Box<dyn Stream<Item = Result<BlockEvent<Event>>> + Send>>,
+  filters: Vec<EventFilter<Event>>,
+}
+
+impl<Event: RuntimeEnumTraits + Send + 'static> EventSubscription<Event> {
+  /// Subscribe to the events of new blocks.
+  ///
+  /// With `finalized` only events from finalized blocks are returned and no
+  /// blocks are skipped.  Otherwise events from new best blocks are returned,
+  /// which can include blocks that don't get finalized.
+  pub async fn new<Api>(api: &Api, finalized: bool) -> Result<Self>
+  where
+    Api: ChainApi<RuntimeEvent = Event> + Send + Sync + 'static,
+  {
+    let client = api.client().clone();
+    let mut sub = if finalized {
+      client.subscribe_finalized_blocks().await?
+    } else {
+      client.subscribe_blocks().await?
+    };
+    let api = api.clone();
+    let stream = try_stream! {
+      let mut last: Option<BlockNumber> = None;
+      while let Some(header) = sub.next().await {
+        let header = header?;
+        let number = header.number;
+        let mut blocks = Vec::new();
+        if finalized {
+          if let Some(last) = last {
+            // Skip blocks already seen (after a reconnect).
+            if number <= last {
+              continue;
+            }
+            // Finality can jump multiple blocks, get the skipped blocks.
+            for num in (last + 1)..number {
+              let hash = client
+                .get_block_hash(num)
+                .await?
+                .ok_or_else(|| Error::RpcClient(format!("Missing block hash for block {num}")))?;
+              blocks.push((hash, num));
+            }
+          }
+          last = Some(number);
+        }
+        blocks.push((header.hash(), number));
+        for (hash, number) in blocks {
+          for event in api.block_events(Some(hash)).await? {
+            yield (hash, number, event);
+          }
+        }
+      }
+    };
+    Ok(Self {
+      stream: Box::pin(stream),
+      filters: Vec::new(),
+    })
+  }
+}
+
+impl<Event: RuntimeEnumTraits> EventSubscription<Event> {
+  /// Add a filter.
+  pub fn filter(mut self, filter: EventFilter<Event>) -> Self {
+    self.filters.push(filter);
+    self
+  }
+
+  /// Only return events from the pallet named `pallet`.
+  ///
+  /// Prefer the typed filters of the generated API, see [`EventFilter`].
+  pub fn pallet(self, pallet: &str) -> Self {
+    self.filter(EventFilter::Pallet(pallet.into()))
+  }
+
+  /// Only return the event named `variant` from the pallet named `pallet`.
+  ///
+  /// Prefer the typed filters of the generated API, see [`EventFilter`].
+  pub fn variant(self, pallet: &str, variant: &str) -> Self {
+    self.filter(EventFilter::Variant(pallet.into(), variant.into()))
+  }
+
+  /// Only return events matching the predicate `f`.
+  pub fn filter_fn<F>(self, f: F) -> Self
+  where
+    F: Fn(&Event) -> bool + Send + Sync + 'static,
+  {
+    self.filter(EventFilter::Custom(Box::new(f)))
+  }
+
+  fn is_match(&self, event: &Event) -> bool {
+    self.filters.is_empty() || self.filters.iter().any(|f| f.matches(event))
+  }
+
+  /// Wait for the next matching event.  Returns `None` when the subscription has ended.
+  pub async fn next(&mut self) -> Option<Result<BlockEvent<Event>>> {
+    core::future::poll_fn(|cx| Pin::new(&mut *self).poll_next(cx)).await
+  }
+}
+
+impl<Event: RuntimeEnumTraits> Stream for EventSubscription<Event> {
+  type Item = Result<BlockEvent<Event>>;
+
+  fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+    loop {
+      match self.stream.as_mut().poll_next(cx) {
+        Poll::Ready(Some(Ok(item))) if !self.is_match(&item.2.event) => continue,
+        res => return res,
+      }
+    }
+  }
+}
+
+impl<Event> core::fmt::Debug for EventSubscription<Event> {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    f.debug_struct("EventSubscription")
+      .field("filters", &self.filters)
+      .finish_non_exhaustive()
+  }
+}
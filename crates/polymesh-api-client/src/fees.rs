@@ -0,0 +1,158 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use sp_weights::Weight;
+
+use crate::*;
+
+#[cfg(feature = "serde")]
+pub mod balance {
+  #[cfg(not(feature = "std"))]
+  use alloc::{format, string::ToString};
+  use serde::de::Error;
+  use serde_json::Value;
+
+  pub fn serialize<S>(balance: &u128, s: S) -> Result<S::Ok, S::Error>
+  where
+    S: serde::Serializer,
+  {
+    s.serialize_str(&balance.to_string())
+  }
+
+  /// Balances can be a number, a decimal string or a hex string.
+  pub fn deserialize<'de, D>(d: D) -> Result<u128, D::Error>
+  where
+    D: serde::Deserializer<'de>,
+  {
+    let val: Value = serde::Deserialize::deserialize(d)?;
+    match &val {
+      Value::Number(num) => num.to_string().parse(),
+      Value::String(s) => match s.strip_prefix("0x") {
+        Some(hex) => u128::from_str_radix(hex, 16),
+        None => s.parse(),
+      },
+      _ => return Err(D::Error::custom(format!("Invalid balance: {val}"))),
+    }
+    .map_err(|e| D::Error::custom(format!("Invalid balance {val}: {e}")))
+  }
+}
+
+/// Dispatch class of a call.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub enum DispatchClass {
+  Normal,
+  Operational,
+  Mandatory,
+}
+
+/// Response from `payment_queryInfo`.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub struct RuntimeDispatchInfo {
+  pub weight: Weight,
+  pub class: DispatchClass,
+  #[cfg_attr(feature = "serde", serde(with = "balance"))]
+  pub partial_fee: u128,
+}
+
+/// The fees for including a transaction in a block.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub struct InclusionFee {
+  /// Minimum fee for a transaction.
+  #[cfg_attr(feature = "serde", serde(with = "balance"))]
+  pub base_fee: u128,
+  /// Fee for the length of the transaction.
+  #[cfg_attr(feature = "serde", serde(with = "balance"))]
+  pub len_fee: u128,
+  /// Weight fee, adjusted by the fee multiplier.
+  #[cfg_attr(feature = "serde", serde(with = "balance"))]
+  pub adjusted_weight_fee: u128,
+}
+
+impl InclusionFee {
+  pub fn total(&self) -> u128 {
+    self
+      .base_fee
+      .saturating_add(self.len_fee)
+      .saturating_add(self.adjusted_weight_fee)
+  }
+}
+
+/// Response from `payment_queryFeeDetails`.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub struct FeeDetails {
+  /// `None` for unsigned transactions.
+  pub inclusion_fee: Option<InclusionFee>,
+  /// The node doesn't return the tip.
+  #[cfg_attr(feature = "serde", serde(default, with = "balance"))]
+  pub tip: u128,
+}
+
+/// Estimated fees for a transaction.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub struct FeeEstimate {
+  pub weight: Weight,
+  pub class: DispatchClass,
+  /// The transaction fee without the tip.
+  #[cfg_attr(feature = "serde", serde(with = "balance"))]
+  pub partial_fee: u128,
+  pub inclusion_fee: Option<InclusionFee>,
+  #[cfg_attr(feature = "serde", serde(with = "balance"))]
+  pub tip: u128,
+}
+
+impl FeeEstimate {
+  pub fn new(info: RuntimeDispatchInfo, details: FeeDetails) -> Self {
+    Self {
+      weight: info.weight,
+      class: info.class,
+      partial_fee: info.partial_fee,
+      inclusion_fee: details.inclusion_fee,
+      tip: details.tip,
+    }
+  }
+
+  /// Total fee including the tip.
+  pub fn total(&self) -> u128 {
+    self.partial_fee.saturating_add(self.tip)
+  }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_decode_fee_responses() -> anyhow::Result<()> {
+    let info: RuntimeDispatchInfo = serde_json::from_str(
+      r#"{"weight":{"ref_time":1000,"proof_size":20},"class":"normal","partialFee":"123456"}"#,
+    )?;
+    assert_eq!(info.weight, Weight::from_parts(1000, 20));
+    assert_eq!(info.class, DispatchClass::Normal);
+    assert_eq!(info.partial_fee, 123456);
+
+    let details: FeeDetails = serde_json::from_str(
+      r#"{"inclusionFee":{"baseFee":100000,"lenFee":"0x3e8","adjustedWeightFee":"22456"}}"#,
+    )?;
+    let fee = details
+      .inclusion_fee
+      .clone()
+      .expect("Missing inclusion fee");
+    assert_eq!(fee.base_fee, 100_000);
+    assert_eq!(fee.len_fee, 1000);
+    assert_eq!(fee.total(), 123456);
+
+    let estimate = FeeEstimate::new(info, details);
+    assert_eq!(estimate.total(), 123456);
+    Ok(())
+  }
+}
@@ -0,0 +1,495 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use base64::Engine;
+use crypto_secretbox::{
+  aead::{Aead, KeyInit},
+  Key, Nonce, XSalsa20Poly1305,
+};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+
+use sp_core::crypto::{Ss58AddressFormat, Ss58Codec};
+use sp_core::{ecdsa, ed25519, sr25519, Pair};
+use sp_runtime::MultiSignature;
+
+use crate::*;
+
+/// The SS58 address format used for new keystore files (Polymesh).
+const SS58_FORMAT: u16 = 12;
+
+const PKCS8_HEADER: [u8; 16] = [48, 83, 2, 1, 1, 48, 5, 6, 3, 43, 101, 112, 4, 34, 4, 32];
+const PKCS8_DIVIDER: [u8; 5] = [161, 35, 3, 33, 0];
+
+const SALT_LEN: usize = 32;
+const NONCE_LEN: usize = 24;
+/// Salt, scrypt parameters (N, p, r) and nonce.
+const PARAMS_LEN: usize = SALT_LEN + 12 + NONCE_LEN;
+
+// Default scrypt parameters used by Polkadot-JS.
+const SCRYPT_N: u32 = 1 << 15;
+const SCRYPT_P: u32 = 1;
+const SCRYPT_R: u32 = 8;
+
+fn keystore_err<E: core::fmt::Display>(msg: &str) -> impl FnOnce(E) -> Error + '_ {
+  move |e| Error::Keystore(format!("{msg}: {e}"))
+}
+
+/// A key pair of any of the supported crypto types.
+#[derive(Clone)]
+pub enum KeystorePair {
+  Sr25519(sr25519::Pair),
+  Ed25519(Box<ed25519::Pair>),
+  Ecdsa(ecdsa::Pair),
+}
+
+impl KeystorePair {
+  /// The crypto type name used by Polkadot-JS.
+  pub fn key_type(&self) -> &'static str {
+    match self {
+      Self::Sr25519(_) => "sr25519",
+      Self::Ed25519(_) => "ed25519",
+      Self::Ecdsa(_) => "ecdsa",
+    }
+  }
+
+  /// Generate a key pair of type `key_type` from a secret URI.
+  pub fn from_suri(key_type: &str, suri: &str, password: Option<&str>) -> Result<Self> {
+    Ok(match key_type {
+      "sr25519" => Self::Sr25519(<sr25519::Pair as Pair>::from_string(suri, password)?),
+      "ed25519" => Self::Ed25519(Box::new(<ed25519::Pair as Pair>::from_string(
+        suri, password,
+      )?)),
+      "ecdsa" => Self::Ecdsa(<ecdsa::Pair as Pair>::from_string(suri, password)?),
+      _ => return Err(Error::Keystore(format!("Unsupported key type: {key_type}"))),
+    })
+  }
+
+  /// Secret and public keys in the Polkadot-JS format.
+  fn to_pkcs8_keys(&self) -> Result<(Vec<u8>, Vec<u8>)> {
+    Ok(match self {
+      Self::Sr25519(pair) => {
+        let secret = schnorrkel::SecretKey::from_bytes(&pair.to_raw_vec())
+          .map_err(keystore_err("Invalid sr25519 secret key"))?;
+        (secret.to_ed25519_bytes().to_vec(), pair.public().0.to_vec())
+      }
+      Self::Ed25519(pair) => {
+        // The secret key is the seed followed by the public key.
+        let public = pair.public().0.to_vec();
+        let mut secret = pair.seed().to_vec();
+        secret.extend_from_slice(&public);
+        (secret, public)
+      }
+      Self::Ecdsa(pair) => (pair.seed().to_vec(), pair.public().0.to_vec()),
+    })
+  }
+
+  /// Decode the key pair from the Polkadot-JS secret key.
+  fn from_pkcs8_keys(key_type: &str, secret: &[u8], public: &[u8]) -> Result<Self> {
+    let pair = match (key_type, secret.len()) {
+      ("sr25519", 64) => {
+        let secret = schnorrkel::SecretKey::from_ed25519_bytes(secret)
+          .map_err(keystore_err("Invalid sr25519 secret key"))?;
+        Self::Sr25519(sr25519::Pair::from_seed_slice(&secret.to_bytes())?)
+      }
+      ("ed25519", 64) => Self::Ed25519(Box::new(ed25519::Pair::from_seed_slice(&secret[..32])?)),
+      ("ecdsa", 32) => Self::Ecdsa(ecdsa::Pair::from_seed_slice(secret)?),
+      _ => {
+        return Err(Error::Keystore(format!(
+          "Invalid {key_type} secret key length: {}",
+          secret.len()
+        )))
+      }
+    };
+    let (_, expected) = pair.to_pkcs8_keys()?;
+    if expected != public {
+      return Err(Error::Keystore(
+        "The public key doesn't match the secret key".into(),
+      ));
+    }
+    Ok(pair)
+  }
+}
+
+impl KeypairSigner for KeystorePair {
+  fn account(&self) -> AccountId {
+    match self {
+      Self::Sr25519(pair) => pair.public().into(),
+      Self::Ed25519(pair) => pair.public().into(),
+      Self::Ecdsa(pair) => AccountId(sp_core::hashing::blake2_256(&pair.public().0)),
+    }
+  }
+
+  fn sign(&self, message: &[u8]) -> MultiSignature {
+    match self {
+      Self::Sr25519(pair) => Pair::sign(pair, message).into(),
+      Self::Ed25519(pair) => Pair::sign(pair.as_ref(), message).into(),
+      Self::Ecdsa(pair) => Pair::sign(pair, message).into(),
+    }
+  }
+
+  /// Generate an sr25519 key pair from a secret URI.  Use [`KeystorePair::from_suri`] for
+  /// the other key types.
+  fn from_string(s: &str, password_override: Option<&str>) -> Result<Self> {
+    Self::from_suri("sr25519", s, password_override)
+  }
+}
+
+/// The encryption of a keystore file.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct KeystoreEncoding {
+  pub content: Vec<String>,
+  #[serde(rename = "type")]
+  pub encoding_type: Vec<String>,
+  pub version: String,
+}
+
+/// Polkadot-JS compatible encrypted JSON keystore file.
+///
+/// The secret key is encrypted with `xsalsa20-poly1305` using a key derived from the
+/// password with `scrypt`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct KeystoreJson {
+  pub encoded: String,
+  pub encoding: KeystoreEncoding,
+  pub address: String,
+  #[serde(default)]
+  pub meta: serde_json::Map<String, serde_json::Value>,
+}
+
+impl KeystoreJson {
+  /// Encrypt the key pair with `password`.
+  pub fn encrypt(pair: &KeystorePair, password: &str, name: Option<&str>) -> Result<Self> {
+    let (secret, public) = pair.to_pkcs8_keys()?;
+    let mut plaintext = Vec::with_capacity(PKCS8_HEADER.len() + secret.len() + 5 + public.len());
+    plaintext.extend_from_slice(&PKCS8_HEADER);
+    plaintext.extend_from_slice(&secret);
+    plaintext.extend_from_slice(&PKCS8_DIVIDER);
+    plaintext.extend_from_slice(&public);
+
+    let mut salt = [0u8; SALT_LEN];
+    let mut nonce = [0u8; NONCE_LEN];
+    rand::rngs::OsRng.fill_bytes(&mut salt);
+    rand::rngs::OsRng.fill_bytes(&mut nonce);
+    let cipher = XSalsa20Poly1305::new(&Self::derive_key(
+      password, &salt, SCRYPT_N, SCRYPT_P, SCRYPT_R,
+    )?);
+    let ciphertext = cipher
+      .encrypt(Nonce::from_slice(&nonce), plaintext.as_slice())
+      .map_err(keystore_err("Encryption failed"))?;
+
+    let mut encoded = Vec::with_capacity(PARAMS_LEN + ciphertext.len());
+    encoded.extend_from_slice(&salt);
+    encoded.extend_from_slice(&SCRYPT_N.to_le_bytes());
+    encoded.extend_from_slice(&SCRYPT_P.to_le_bytes());
+    encoded.extend_from_slice(&SCRYPT_R.to_le_bytes());
+    encoded.extend_from_slice(&nonce);
+    encoded.extend_from_slice(&ciphertext);
+
+    let mut meta = serde_json::Map::new();
+    if let Some(name) = name {
+      meta.insert("name".into(), name.into());
+    }
+    if let Ok(now) = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH) {
+      meta.insert("whenCreated".into(), (now.as_millis() as u64).into());
+    }
+    Ok(Self {
+      encoded: base64::engine::general_purpose::STANDARD.encode(encoded),
+      encoding: KeystoreEncoding {
+        content: vec!["pkcs8".into(), pair.key_type().into()],
+        encoding_type: vec!["scrypt".into(), "xsalsa20-poly1305".into()],
+        version: "3".into(),
+      },
+      address: pair
+        .account()
+        .to_ss58check_with_version(Ss58AddressFormat::custom(SS58_FORMAT)),
+      meta,
+    })
+  }
+
+  fn derive_key(password: &str, salt: &[u8], n: u32, p: u32, r: u32) -> Result<Key> {
+    // Only allow the default parameters, to protect against files that would take
+    // too much cpu/memory to decrypt.
+    if n != SCRYPT_N || p != SCRYPT_P || r != SCRYPT_R {
+      return Err(Error::Keystore(format!(
+        "Unsupported scrypt parameters: N={n}, p={p}, r={r}"
+      )));
+    }
+    let params = scrypt::Params::new(n.ilog2() as u8, r, p, 32)
+      .map_err(keystore_err("Invalid scrypt parameters"))?;
+    let mut key = Key::default();
+    scrypt::scrypt(password.as_bytes(), salt, &params, &mut key)
+      .map_err(keystore_err("Scrypt failed"))?;
+    Ok(key)
+  }
+
+  /// The crypto type of the key.
+  pub fn key_type(&self) -> Result<&str> {
+    ["sr25519", "ed25519", "ecdsa"]
+      .into_iter()
+      .find(|t| self.encoding.content.iter().any(|c| c == t))
+      .ok_or_else(|| Error::Keystore(format!("Unsupported key type: {:?}", self.encoding.content)))
+  }
+
+  /// The account of the key.
+  pub fn account(&self) -> Result<AccountId> {
+    // Allow any SS58 format.
+    let (account, _) = AccountId::from_ss58check_with_version(&self.address)?;
+    Ok(account)
+  }
+
+  /// The name of the key from the metadata.
+  pub fn name(&self) -> Option<&str> {
+    self.meta.get("name").and_then(|name| name.as_str())
+  }
+
+  /// Decrypt the key pair with `password`.
+  pub fn decrypt(&self, password: &str) -> Result<KeystorePair> {
+    let key_type = self.key_type()?;
+    if self.encoding.version != "3"
+      || !self.encoding.content.iter().any(|c| c == "pkcs8")
+      || self.encoding.encoding_type != ["scrypt", "xsalsa20-poly1305"]
+    {
+      return Err(Error::Keystore(format!(
+        "Unsupported encoding: {:?}",
+        self.encoding
+      )));
+    }
+    let encoded = base64::engine::general_purpose::STANDARD
+      .decode(&self.encoded)
+      .map_err(keystore_err("Invalid base64"))?;
+    if encoded.len() < PARAMS_LEN {
+      return Err(Error::Keystore("Encoded key too short".into()));
+    }
+    let (params, ciphertext) = encoded.split_at(PARAMS_LEN);
+    let (salt, params) = params.split_at(SALT_LEN);
+    let u32_at = |idx: usize| u32::from_le_bytes(params[idx..idx + 4].try_into().expect("4 bytes"));
+    let key = Self::derive_key(password, salt, u32_at(0), u32_at(4), u32_at(8))?;
+    let nonce = Nonce::from_slice(&params[12..]);
+    let plaintext = XSalsa20Poly1305::new(&key)
+      .decrypt(nonce, ciphertext)
+      .map_err(|_| Error::Keystore("Wrong password or corrupt keystore".into()))?;
+
+    // Header, secret key, divider and public key.
+    let invalid = || Error::Keystore("Invalid decrypted key".into());
+    let keys = plaintext
+      .strip_prefix(&PKCS8_HEADER[..])
+      .ok_or_else(invalid)?;
+    let div = keys
+      .windows(PKCS8_DIVIDER.len())
+      .position(|w| w == PKCS8_DIVIDER)
+      .ok_or_else(invalid)?;
+    let (secret, public) = (&keys[..div], &keys[div + PKCS8_DIVIDER.len()..]);
+    let pair = KeystorePair::from_pkcs8_keys(key_type, secret, public)?;
+    if pair.account() != self.account()? {
+      return Err(Error::Keystore(
+        "The key doesn't match the keystore address".into(),
+      ));
+    }
+    Ok(pair)
+  }
+
+  /// Load a keystore file.
+  pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+    let data = fs::read_to_string(path)?;
+    Ok(serde_json::from_str(&data)?)
+  }
+
+  /// Save to a keystore file.
+  pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+    fs::write(path, serde_json::to_string_pretty(self)?)?;
+    Ok(())
+  }
+}
+
+impl PairSigner<KeystorePair> {
+  /// Load the signer from an encrypted keystore file.
+  pub fn from_keystore_file<P: AsRef<Path>>(path: P, password: &str) -> Result<Self> {
+    let pair = KeystoreJson::load(path)?.decrypt(password)?;
+    Ok(Self::new(pair))
+  }
+}
+
+/// A directory of keystore files.  The files are named by the SS58 address of the key.
+#[derive(Clone, Debug)]
+pub struct DirKeystore {
+  path: PathBuf,
+}
+
+impl DirKeystore {
+  /// Open the keystore directory.  The directory is created when the first key is added.
+  pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+    Ok(Self {
+      path: path.as_ref().to_path_buf(),
+    })
+  }
+
+  pub fn path(&self) -> &Path {
+    &self.path
+  }
+
+  fn key_path(&self, account: &AccountId) -> PathBuf {
+    let address = account.to_ss58check_with_version(Ss58AddressFormat::custom(SS58_FORMAT));
+    self.path.join(format!("{address}.json"))
+  }
+
+  /// List the keystore files in the directory.
+  pub fn list(&self) -> Result<Vec<KeystoreJson>> {
+    let mut keys = Vec::new();
+    if !self.path.exists() {
+      return Ok(keys);
+    }
+    for entry in fs::read_dir(&self.path)? {
+      let path = entry?.path();
+      if path.extension().is_none_or(|ext| ext != "json") {
+        continue;
+      }
+      match KeystoreJson::load(&path) {
+        Ok(key) => keys.push(key),
+        Err(err) => {
+          log::warn!("Skipping invalid keystore file {path:?}: {err:?}");
+        }
+      }
+    }
+    keys.sort_by(|a, b| a.address.cmp(&b.address));
+    Ok(keys)
+  }
+
+  /// List the accounts in the keystore.
+  pub fn accounts(&self) -> Result<Vec<AccountId>> {
+    Ok(
+      self
+        .list()?
+        .iter()
+        .filter_map(|key| match key.account() {
+          Ok(account) => Some(account),
+          Err(err) => {
+            log::warn!(
+              "Skipping keystore file with invalid address {:?}: {err:?}",
+              key.address
+            );
+            None
+          }
+        })
+        .collect(),
+    )
+  }
+
+  pub fn contains(&self, account: &AccountId) -> bool {
+    self.key_path(account).exists()
+  }
+
+  /// Encrypt the key pair and add it to the keystore.  Returns the path of the keystore file.
+  pub fn insert(&self, pair: &KeystorePair, password: &str, name: Option<&str>) -> Result<PathBuf> {
+    fs::create_dir_all(&self.path)?;
+    let path = self.key_path(&pair.account());
+    KeystoreJson::encrypt(pair, password, name)?.save(&path)?;
+    Ok(path)
+  }
+
+  /// Get the keystore file of `account`.
+  pub fn get(&self, account: &AccountId) -> Result<KeystoreJson> {
+    let path = self.key_path(account);
+    if !path.exists() {
+      return Err(Error::Keystore(format!(
+        "Account {account} not found in keystore"
+      )));
+    }
+    KeystoreJson::load(path)
+  }
+
+  /// Decrypt the key pair of `account`.
+  pub fn load(&self, account: &AccountId, password: &str) -> Result<KeystorePair> {
+    self.get(account)?.decrypt(password)
+  }
+
+  /// Get a signer for `account`.
+  pub fn signer(&self, account: &AccountId, password: &str) -> Result<PairSigner<KeystorePair>> {
+    Ok(PairSigner::new(self.load(account, password)?))
+  }
+
+  /// Remove the key of `account` from the keystore.
+  pub fn remove(&self, account: &AccountId) -> Result<()> {
+    fs::remove_file(self.key_path(account))?;
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// Alice's key exported from Polkadot-JS.  Password: "whoisalice"
+  const POLKADOT_JS_KEY: &str = r#"{
+    "encoded": "DumgApKCTqoCty1OZW/8WS+sgo6RdpHhCwAkA2IoDBMAgAAAAQAAAAgAAAB6IG/q24EeVf0JqWqcBd5m2tKq5BlyY84IQ8oamLn9DZe9Ouhgunr7i36J1XxUnTI801axqL/ym1gil0U8440Qvj0lFVKwGuxq38zuifgoj0B3Yru0CI6QKEvQPU5xxj4MpyxdSxP+2PnTzYao0HDH0fulaGvlAYXfqtU89xrx2/z9z7IjSwS3oDFPXRQ9kAdDebtyCVreZ9Otw9v3",
+    "encoding": {
+      "content": ["pkcs8", "sr25519"],
+      "type": ["scrypt", "xsalsa20-poly1305"],
+      "version": "3"
+    },
+    "address": "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY",
+    "meta": { "genesisHash": "", "name": "Alice", "whenCreated": 1718265838755 }
+  }"#;
+
+  #[test]
+  fn test_decrypt_polkadot_js() -> anyhow::Result<()> {
+    let json: KeystoreJson = serde_json::from_str(POLKADOT_JS_KEY)?;
+    assert_eq!(json.name(), Some("Alice"));
+    let pair = json.decrypt("whoisalice")?;
+    assert_eq!(pair.key_type(), "sr25519");
+    assert_eq!(pair.account(), KeypairSigner::account(&dev::alice().pair));
+    assert!(json.decrypt("wrong password").is_err());
+    Ok(())
+  }
+
+  #[test]
+  fn test_encrypt_roundtrip() -> anyhow::Result<()> {
+    for key_type in ["sr25519", "ed25519", "ecdsa"] {
+      let pair = KeystorePair::from_suri(key_type, "//Alice", None)?;
+      let json = KeystoreJson::encrypt(&pair, "password", Some("Alice"))?;
+      assert_eq!(json.key_type()?, key_type);
+      assert_eq!(json.account()?, pair.account());
+
+      let decoded = json.decrypt("password")?;
+      assert_eq!(decoded.account(), pair.account());
+      let sig = KeypairSigner::sign(&decoded, b"message");
+      assert!(pair.verify(&sig, b"message")?);
+    }
+    Ok(())
+  }
+
+  #[test]
+  fn test_dir_keystore() -> anyhow::Result<()> {
+    let dir = std::env::temp_dir().join(format!("polymesh-keystore-{}", std::process::id()));
+    let keystore = DirKeystore::open(&dir)?;
+    // The directory is only created when a key is added.
+    assert!(keystore.accounts()?.is_empty());
+    assert!(!dir.exists());
+    let alice = KeystorePair::from_suri("sr25519", "//Alice", None)?;
+    let bob = KeystorePair::from_suri("ed25519", "//Bob", None)?;
+    keystore.insert(&alice, "alice", Some("Alice"))?;
+    keystore.insert(&bob, "bob", Some("Bob"))?;
+
+    let mut expected = vec![alice.account(), bob.account()];
+    expected.sort_by_key(|account| {
+      account.to_ss58check_with_version(Ss58AddressFormat::custom(SS58_FORMAT))
+    });
+    assert_eq!(keystore.accounts()?, expected);
+
+    // Invalid files are skipped.
+    fs::write(dir.join("invalid.json"), "not json")?;
+    let mut invalid_address = KeystoreJson::encrypt(&alice, "alice", None)?;
+    invalid_address.address = "invalid".into();
+    invalid_address.save(dir.join("invalid_address.json"))?;
+    assert_eq!(keystore.accounts()?, expected);
+
+    let signer = keystore.signer(&bob.account(), "bob")?;
+    assert_eq!(signer.account, bob.account());
+    assert!(keystore.load(&alice.account(), "bob").is_err());
+
+    keystore.remove(&alice.account())?;
+    assert!(!keystore.contains(&alice.account()));
+    fs::remove_dir_all(&dir)?;
+    Ok(())
+  }
+}
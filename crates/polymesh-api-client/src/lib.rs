@@ -21,15 +21,55 @@ pub mod lockable_signer;
 #[cfg(feature = "std")]
 pub use lockable_signer::*;
 
+#[cfg(feature = "keystore")]
+pub mod keystore;
+#[cfg(feature = "keystore")]
+pub use keystore::*;
+
+#[cfg(all(feature = "remote_signer", not(target_arch = "wasm32")))]
+pub mod remote_signer;
+#[cfg(all(feature = "remote_signer", not(target_arch = "wasm32")))]
+pub use remote_signer::*;
+
+#[cfg(feature = "metadata_hash")]
+pub mod metadata_hash;
+#[cfg(feature = "metadata_hash")]
+pub use metadata_hash::*;
+
+#[cfg(feature = "std")]
+pub mod nonce_manager;
+#[cfg(feature = "std")]
+pub use nonce_manager::*;
+
+#[cfg(all(feature = "mock", not(target_arch = "wasm32")))]
+pub mod mock;
+#[cfg(all(feature = "mock", not(target_arch = "wasm32")))]
+pub use mock::MockNode;
+
 pub mod transaction;
 pub use transaction::*;
 
 pub mod storage;
 pub use storage::*;
 
+pub mod snapshot;
+pub use snapshot::*;
+
+pub mod events;
+pub use events::*;
+
+pub mod fees;
+pub use fees::*;
+
+pub mod runtime_api;
+pub use runtime_api::*;
+
 pub mod client;
 pub use client::*;
 
+pub mod compat;
+pub use compat::*;
+
 #[cfg(feature = "serde")]
 pub mod serde_impl;
 
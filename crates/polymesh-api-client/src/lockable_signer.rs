@@ -52,10 +52,18 @@ impl<S: Signer> Signer for LockedSigner<S> {
     self.0.nonce().await
   }
 
+  async fn reserve_nonce(&self) -> Option<u32> {
+    self.0.reserve_nonce().await
+  }
+
   async fn set_nonce(&mut self, nonce: u32) {
     self.0.set_nonce(nonce).await
   }
 
+  fn nonce_rollback(&self) -> Option<Arc<dyn NonceRollback>> {
+    self.0.nonce_rollback()
+  }
+
   async fn sign(&self, msg: &[u8]) -> Result<MultiSignature> {
     self.0.sign(msg).await
   }
@@ -22,6 +22,11 @@ use sp_std::prelude::*;
 mod storage;
 pub use storage::*;
 
+#[cfg(feature = "v15")]
+mod runtime_api;
+#[cfg(feature = "v15")]
+pub use runtime_api::*;
+
 use crate::error::*;
 use crate::schema::*;
 use crate::type_def::*;
@@ -41,22 +46,76 @@ pub(crate) fn decode_meta<B: Encode + 'static, O: Decode + Clone + 'static>(
   }
 }
 
+/// Get the encoded value of a `System` pallet constant from V14 or V15 chain metadata.
+#[cfg(feature = "v14")]
+pub fn system_constant<'a>(
+  metadata: &'a frame_metadata::RuntimeMetadata,
+  name: &str,
+) -> Result<&'a [u8]> {
+  use frame_metadata::RuntimeMetadata;
+  let value = match metadata {
+    RuntimeMetadata::V14(md) => md
+      .pallets
+      .iter()
+      .filter(|pallet| pallet.name == "System")
+      .flat_map(|pallet| pallet.constants.iter())
+      .find(|constant| constant.name == name)
+      .map(|constant| constant.value.as_slice()),
+    RuntimeMetadata::V15(md) => md
+      .pallets
+      .iter()
+      .filter(|pallet| pallet.name == "System")
+      .flat_map(|pallet| pallet.constants.iter())
+      .find(|constant| constant.name == name)
+      .map(|constant| constant.value.as_slice()),
+    md => {
+      return Err(Error::MetadataParseFailed(format!(
+        "Unsupported metadata version: {}",
+        md.version()
+      )))
+    }
+  };
+  value.ok_or_else(|| Error::MetadataParseFailed(format!("Missing `System.{name}` constant")))
+}
+
+/// Get the runtime `spec_version` from the `System.Version` constant.
+#[cfg(feature = "v14")]
+pub fn metadata_spec_version(metadata: &frame_metadata::RuntimeMetadata) -> Result<u32> {
+  use codec::Decode;
+  let mut version = system_constant(metadata, "Version")?;
+  // Only decode the start of `RuntimeVersion`: spec_name, impl_name, authoring_version, spec_version.
+  let (_, _, _, spec_version) = <(String, String, u32, u32)>::decode(&mut version)?;
+  Ok(spec_version)
+}
+
 #[derive(Clone)]
 pub struct Metadata {
   modules: BTreeMap<String, ModuleMetadata>,
   idx_map: BTreeMap<u8, String>,
+  #[cfg(feature = "v15")]
+  apis: BTreeMap<String, RuntimeApiMetadata>,
+  #[cfg(feature = "v15")]
+  outer_enums: Option<OuterEnums>,
 }
 
 impl Metadata {
+  fn new() -> Self {
+    Self {
+      modules: BTreeMap::new(),
+      idx_map: BTreeMap::new(),
+      #[cfg(feature = "v15")]
+      apis: BTreeMap::new(),
+      #[cfg(feature = "v15")]
+      outer_enums: None,
+    }
+  }
+
   #[cfg(feature = "v12")]
   pub fn from_v12_metadata(
     md: frame_metadata::v12::RuntimeMetadataV12,
     lookup: &mut Types,
   ) -> Result<Self> {
-    let mut api_md = Self {
-      modules: BTreeMap::new(),
-      idx_map: BTreeMap::new(),
-    };
+    let mut api_md = Self::new();
 
     // Top-level event/error/call types.
     let mut mod_events = TypeDefVariant::new();
@@ -92,10 +151,7 @@ impl Metadata {
     md: frame_metadata::v13::RuntimeMetadataV13,
     lookup: &mut Types,
   ) -> Result<Self> {
-    let mut api_md = Self {
-      modules: BTreeMap::new(),
-      idx_map: BTreeMap::new(),
-    };
+    let mut api_md = Self::new();
 
     // Top-level event/error/call types.
     let mut mod_events = TypeDefVariant::new();
@@ -131,15 +187,48 @@ impl Metadata {
     md: frame_metadata::v14::RuntimeMetadataV14,
     lookup: &mut Types,
   ) -> Result<Self> {
-    let mut api_md = Self {
-      modules: BTreeMap::new(),
-      idx_map: BTreeMap::new(),
-    };
+    let types = PortableRegistry::from(&md.types);
+    let modules = md
+      .pallets
+      .iter()
+      .map(|m| ModuleMetadata::from_v14_meta(m, &types))
+      .collect::<Result<Vec<_>>>()?;
+
+    Self::from_portable_modules(Self::new(), &types, modules, lookup)
+  }
 
+  #[cfg(feature = "v15")]
+  pub fn from_v15_metadata(
+    md: frame_metadata::v15::RuntimeMetadataV15,
+    lookup: &mut Types,
+  ) -> Result<Self> {
     let types = PortableRegistry::from(&md.types);
+    let modules = md
+      .pallets
+      .iter()
+      .map(|m| ModuleMetadata::from_v15_meta(m, &types))
+      .collect::<Result<Vec<_>>>()?;
+
+    let mut api_md = Self::new();
+    for api in &md.apis {
+      let api = RuntimeApiMetadata::from_v15_meta(api, &types)?;
+      api_md.apis.insert(api.name.clone(), api);
+    }
+    api_md.outer_enums = Some(OuterEnums::from_v15_meta(&md.outer_enums));
 
+    Self::from_portable_modules(api_md, &types, modules, lookup)
+  }
+
+  /// Build the top-level event/error/call types from the pallets of V14+ metadata.
+  #[cfg(feature = "v14")]
+  fn from_portable_modules(
+    mut api_md: Self,
+    types: &PortableRegistry,
+    modules: Vec<ModuleMetadata>,
+    lookup: &mut Types,
+  ) -> Result<Self> {
     // Import types from registry.
-    lookup.import_v14_types(&types)?;
+    lookup.import_v14_types(types)?;
 
     // Top-level event/error/call types.
     let mut mod_events = TypeDefVariant::new();
@@ -147,8 +236,7 @@ impl Metadata {
     let mut mod_calls = TypeDefVariant::new();
 
     // Decode module metadata.
-    md.pallets.iter().try_for_each(|m| -> Result<()> {
-      let m = ModuleMetadata::from_v14_meta(m, &types)?;
+    modules.into_iter().try_for_each(|m| -> Result<()> {
       let name = m.name.clone();
       mod_events.insert(m.index, &name, m.event_ref.clone());
       mod_errors.insert(m.index, &name, m.error_ref.clone());
@@ -188,6 +276,23 @@ impl Metadata {
   pub fn modules(&self) -> impl Iterator<Item = &ModuleMetadata> {
     self.modules.values()
   }
+
+  /// Get a runtime API by name (e.g. `Metadata`).  Only V15+ metadata has runtime APIs.
+  #[cfg(feature = "v15")]
+  pub fn get_runtime_api(&self, name: &str) -> Option<&RuntimeApiMetadata> {
+    self.apis.get(name)
+  }
+
+  #[cfg(feature = "v15")]
+  pub fn runtime_apis(&self) -> impl Iterator<Item = &RuntimeApiMetadata> {
+    self.apis.values()
+  }
+
+  /// The type ids of the runtime's call/event/error enums.  Only V15+ metadata has these.
+  #[cfg(feature = "v15")]
+  pub fn outer_enums(&self) -> Option<&OuterEnums> {
+    self.outer_enums.as_ref()
+  }
 }
 
 #[derive(Clone)]
@@ -390,10 +495,46 @@ impl ModuleMetadata {
     md: &frame_metadata::v14::PalletMetadata<PortableForm>,
     types: &PortableRegistry,
   ) -> Result<Self> {
-    let mod_idx = md.index;
-    let mod_name = &md.name;
+    Self::from_pallet_parts(
+      &md.name,
+      md.index,
+      PalletParts {
+        calls: md.calls.as_ref(),
+        event: md.event.as_ref(),
+        error: md.error.as_ref(),
+        storage: md.storage.as_ref(),
+      },
+      types,
+    )
+  }
+
+  #[cfg(feature = "v15")]
+  fn from_v15_meta(
+    md: &frame_metadata::v15::PalletMetadata<PortableForm>,
+    types: &PortableRegistry,
+  ) -> Result<Self> {
+    Self::from_pallet_parts(
+      &md.name,
+      md.index,
+      PalletParts {
+        calls: md.calls.as_ref(),
+        event: md.event.as_ref(),
+        error: md.error.as_ref(),
+        storage: md.storage.as_ref(),
+      },
+      types,
+    )
+  }
+
+  #[cfg(feature = "v14")]
+  fn from_pallet_parts(
+    mod_name: &str,
+    mod_idx: u8,
+    md: PalletParts,
+    types: &PortableRegistry,
+  ) -> Result<Self> {
     let mut module = Self {
-      name: mod_name.clone(),
+      name: mod_name.into(),
       index: mod_idx,
       funcs: BTreeMap::new(),
       events: BTreeMap::new(),
@@ -406,7 +547,7 @@ impl ModuleMetadata {
     };
 
     // Decode module functions.
-    if let Some(calls) = &md.calls {
+    if let Some(calls) = md.calls {
       let id = calls.ty.id;
       module.call_ref = Some(id.into());
 
@@ -427,7 +568,7 @@ impl ModuleMetadata {
     }
 
     // Decode module events.
-    if let Some(events) = &md.event {
+    if let Some(events) = md.event {
       let id = events.ty.id;
       module.event_ref = Some(id.into());
 
@@ -448,7 +589,7 @@ impl ModuleMetadata {
     }
 
     // Decode module errors.
-    if let Some(error) = &md.error {
+    if let Some(error) = md.error {
       let id = error.ty.id;
       module.error_ref = Some(id.into());
 
@@ -470,7 +611,7 @@ impl ModuleMetadata {
     }
 
     // Parse storage metadata
-    if let Some(storage) = &md.storage {
+    if let Some(storage) = md.storage {
       module.storage = Some(StorageMetadata::from_v14_meta(storage, types)?);
     }
 
@@ -478,6 +619,15 @@ impl ModuleMetadata {
   }
 }
 
+/// The pallet parts shared by the V14 and V15 pallet metadata.
+#[cfg(feature = "v14")]
+struct PalletParts<'a> {
+  calls: Option<&'a frame_metadata::v14::PalletCallMetadata<PortableForm>>,
+  event: Option<&'a frame_metadata::v14::PalletEventMetadata<PortableForm>>,
+  error: Option<&'a frame_metadata::v14::PalletErrorMetadata<PortableForm>>,
+  storage: Option<&'a frame_metadata::v14::PalletStorageMetadata<PortableForm>>,
+}
+
 #[derive(Debug, Clone)]
 pub struct NamedType {
   pub name: String,
@@ -495,6 +645,18 @@ impl NamedType {
     Ok(named)
   }
 
+  /// Named type for a type id without a type name (e.g. runtime API parameters).
+  #[cfg(feature = "v14")]
+  pub fn new_type_id(ty_id: TypeId, types: &PortableRegistry) -> Result<Self> {
+    let ty = types
+      .resolve(ty_id)
+      .ok_or_else(|| Error::MetadataParseFailed("Failed to resolve type.".into()))?;
+    Ok(Self {
+      name: get_type_name(ty, types, false),
+      ty_id,
+    })
+  }
+
   #[cfg(feature = "v14")]
   pub fn new_field_type(md: &Field, types: &PortableRegistry) -> Result<Self> {
     let ty = types
@@ -880,3 +1042,134 @@ impl Docs {
     }
   }
 }
+
+#[cfg(all(test, feature = "v15"))]
+mod tests {
+  use super::*;
+
+  use codec::{Decode, Encode};
+  use frame_metadata::{v14, v15, RuntimeMetadata, RuntimeMetadataPrefixed};
+
+  fn find_type(
+    md: &v14::RuntimeMetadataV14,
+    f: impl Fn(&scale_info::Type<PortableForm>) -> bool,
+  ) -> u32 {
+    md.types
+      .types
+      .iter()
+      .find(|ty| f(&ty.ty))
+      .map(|ty| ty.id)
+      .expect("Missing type")
+  }
+
+  fn find_path(md: &v14::RuntimeMetadataV14, path: &str) -> u32 {
+    find_type(md, |ty| ty.path.segments.join("::") == path)
+  }
+
+  /// The spec files are V14, convert one to V15 and add a runtime API.
+  fn v15_metadata() -> v15::RuntimeMetadataV15 {
+    let spec = include_bytes!("../../../specs/polymesh_dev_spec_8000001.meta");
+    let md = match RuntimeMetadataPrefixed::decode(&mut &spec[..])
+      .expect("metadata")
+      .1
+    {
+      RuntimeMetadata::V14(md) => md,
+      _ => panic!("Expected V14 metadata"),
+    };
+    let account_ty = find_path(&md, "sp_core::crypto::AccountId32");
+    let u32_ty = find_type(&md, |ty| {
+      ty.type_def == scale_info::TypeDef::Primitive(scale_info::TypeDefPrimitive::U32)
+    });
+    let call_enum_ty = find_path(&md, "polymesh_runtime_develop::runtime::RuntimeCall");
+    let event_enum_ty = find_path(&md, "polymesh_runtime_develop::runtime::RuntimeEvent");
+    // V14 doesn't have a `RuntimeError` type.
+    let error_enum_ty = find_path(&md, "sp_runtime::DispatchError");
+    v15::RuntimeMetadataV15 {
+      types: md.types,
+      pallets: md
+        .pallets
+        .into_iter()
+        .map(|p| v15::PalletMetadata {
+          name: p.name,
+          storage: p.storage,
+          calls: p.calls,
+          event: p.event,
+          constants: p.constants,
+          error: p.error,
+          index: p.index,
+          docs: vec![],
+        })
+        .collect(),
+      extrinsic: v15::ExtrinsicMetadata {
+        version: md.extrinsic.version,
+        address_ty: account_ty.into(),
+        call_ty: call_enum_ty.into(),
+        signature_ty: account_ty.into(),
+        extra_ty: account_ty.into(),
+        signed_extensions: vec![],
+      },
+      ty: md.ty,
+      apis: vec![v15::RuntimeApiMetadata {
+        name: "AccountNonceApi".into(),
+        methods: vec![v15::RuntimeApiMethodMetadata {
+          name: "account_nonce".into(),
+          inputs: vec![v15::RuntimeApiMethodParamMetadata {
+            name: "account".into(),
+            ty: account_ty.into(),
+          }],
+          output: u32_ty.into(),
+          docs: vec![" Get current account nonce of given `AccountId`.".into()],
+        }],
+        docs: vec![],
+      }],
+      outer_enums: v15::OuterEnums {
+        call_enum_ty: call_enum_ty.into(),
+        event_enum_ty: event_enum_ty.into(),
+        error_enum_ty: error_enum_ty.into(),
+      },
+      custom: v15::CustomMetadata {
+        map: Default::default(),
+      },
+    }
+  }
+
+  #[test]
+  fn test_v15_metadata() -> anyhow::Result<()> {
+    let md = v15_metadata();
+    let call_enum_ty = md.outer_enums.call_enum_ty.id;
+    // Check that the V15 metadata round-trips through the prefixed encoding.
+    let prefixed = RuntimeMetadataPrefixed::from(md).encode();
+    let md = match RuntimeMetadataPrefixed::decode(&mut &prefixed[..])?.1 {
+      RuntimeMetadata::V15(md) => md,
+      _ => panic!("Expected V15 metadata"),
+    };
+
+    let mut types = Types::new(RuntimeVersion::default());
+    let metadata = Metadata::from_v15_metadata(md, &mut types)?;
+    assert!(metadata.get_module("Balances").is_some());
+
+    let api = metadata
+      .get_runtime_api("AccountNonceApi")
+      .expect("Missing runtime API");
+    let method = api.get_method("account_nonce").expect("Missing method");
+    assert_eq!(method.method(), "AccountNonceApi_account_nonce");
+    assert_eq!(method.inputs.len(), 1);
+    assert_eq!(method.inputs[0].name, "account");
+    assert_eq!(method.output.name, "u32");
+
+    let outer_enums = metadata.outer_enums().expect("Missing outer enums");
+    assert_eq!(outer_enums.call_enum_ty, call_enum_ty.into());
+    Ok(())
+  }
+
+  #[test]
+  fn test_metadata_spec_version() -> anyhow::Result<()> {
+    let spec = include_bytes!("../../../specs/polymesh_dev_spec_7004000.meta");
+    let md = RuntimeMetadataPrefixed::decode(&mut &spec[..])?.1;
+    assert_eq!(metadata_spec_version(&md)?, 7004000);
+    let md = RuntimeMetadata::V15(v15_metadata());
+    assert_eq!(metadata_spec_version(&md)?, 8000001);
+    assert!(system_constant(&md, "Missing").is_err());
+    Ok(())
+  }
+}
@@ -0,0 +1,117 @@
+use super::*;
+
+/// Metadata for a runtime API (e.g. `Metadata`, `TransactionPaymentApi`).
+///
+/// Runtime APIs are only available in V15+ metadata.
+#[derive(Clone)]
+pub struct RuntimeApiMetadata {
+  /// The name of the runtime API trait.
+  pub name: String,
+  /// The methods of the runtime API, keyed by method name.
+  pub methods: BTreeMap<String, RuntimeApiMethodMetadata>,
+  pub docs: Docs,
+}
+
+impl RuntimeApiMetadata {
+  /// Creates runtime API metadata from V15 metadata format.
+  ///
+  /// # Arguments
+  ///
+  /// * `md` - The V15 runtime API metadata
+  /// * `types` - Portable type registry for resolving type references
+  ///
+  /// # Returns
+  ///
+  /// The parsed runtime API metadata, or an error if parsing fails.
+  pub fn from_v15_meta(
+    md: &frame_metadata::v15::RuntimeApiMetadata<PortableForm>,
+    types: &PortableRegistry,
+  ) -> Result<Self> {
+    let mut methods = BTreeMap::new();
+    md.methods.iter().try_for_each(|method| -> Result<()> {
+      let method = RuntimeApiMethodMetadata::from_v15_meta(&md.name, method, types)?;
+      methods.insert(method.name.clone(), method);
+      Ok(())
+    })?;
+
+    Ok(Self {
+      name: md.name.clone(),
+      methods,
+      docs: Docs::from_v14_meta(&md.docs),
+    })
+  }
+
+  /// Get a method of this runtime API by name.
+  pub fn get_method(&self, name: &str) -> Option<&RuntimeApiMethodMetadata> {
+    self.methods.get(name)
+  }
+}
+
+/// Metadata for a runtime API method.
+#[derive(Clone)]
+pub struct RuntimeApiMethodMetadata {
+  /// The name of the runtime API trait this method belongs to.
+  pub api_name: String,
+  /// The method name.
+  pub name: String,
+  /// The method parameters, SCALE encoded in order for the `state_call` RPC.
+  pub inputs: Vec<FuncArg>,
+  /// The type returned by the method.
+  pub output: NamedType,
+  pub docs: Docs,
+}
+
+impl RuntimeApiMethodMetadata {
+  /// Creates runtime API method metadata from V15 metadata format.
+  pub fn from_v15_meta(
+    api_name: &str,
+    md: &frame_metadata::v15::RuntimeApiMethodMetadata<PortableForm>,
+    types: &PortableRegistry,
+  ) -> Result<Self> {
+    let inputs = md
+      .inputs
+      .iter()
+      .map(|param| -> Result<FuncArg> {
+        Ok(FuncArg {
+          name: param.name.clone(),
+          ty: NamedType::new_type_id(param.ty.id.into(), types)?,
+        })
+      })
+      .collect::<Result<Vec<_>>>()?;
+
+    Ok(Self {
+      api_name: api_name.into(),
+      name: md.name.clone(),
+      inputs,
+      output: NamedType::new_type_id(md.output.id.into(), types)?,
+      docs: Docs::from_v14_meta(&md.docs),
+    })
+  }
+
+  /// The runtime method name used with the `state_call` RPC (e.g. `Metadata_metadata_at_version`).
+  pub fn method(&self) -> String {
+    format!("{}_{}", self.api_name, self.name)
+  }
+}
+
+/// The type ids of the runtime's outer call/event/error enums.
+#[derive(Clone, Copy, Debug)]
+pub struct OuterEnums {
+  /// The `RuntimeCall` type.
+  pub call_enum_ty: TypeId,
+  /// The `RuntimeEvent` type.
+  pub event_enum_ty: TypeId,
+  /// The `RuntimeError` type.
+  pub error_enum_ty: TypeId,
+}
+
+impl OuterEnums {
+  /// Creates the outer enum type ids from V15 metadata format.
+  pub fn from_v15_meta(md: &frame_metadata::v15::OuterEnums<PortableForm>) -> Self {
+    Self {
+      call_enum_ty: md.call_enum_ty.id.into(),
+      event_enum_ty: md.event_enum_ty.id.into(),
+      error_enum_ty: md.error_enum_ty.id.into(),
+    }
+  }
+}
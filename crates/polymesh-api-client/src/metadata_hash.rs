@@ -0,0 +1,215 @@
+#[cfg(not(feature = "std"))]
+use alloc::{format, string::String};
+use sp_std::prelude::*;
+
+use codec::Decode;
+use sp_core::H256;
+
+use frame_metadata::{
+  v14::RuntimeMetadataV14,
+  v15::{
+    CustomMetadata, ExtrinsicMetadata, OuterEnums, PalletMetadata, RuntimeMetadataV15,
+    SignedExtensionMetadata,
+  },
+  RuntimeMetadata, RuntimeMetadataPrefixed,
+};
+use merkleized_metadata::{generate_metadata_digest, ExtraInfo};
+
+use crate::metadata::system_constant;
+use crate::*;
+
+/// Check if the runtime has the `CheckMetadataHash` signed extension.
+///
+/// `metadata` is the SCALE encoded `RuntimeMetadataPrefixed`.
+pub fn has_check_metadata_hash(metadata: &[u8]) -> Result<bool> {
+  let metadata = RuntimeMetadataPrefixed::decode(&mut &metadata[..])
+    .map_err(|e| Error::MetadataHash(format!("Invalid metadata: {e:?}")))?
+    .1;
+  let is_check = |identifier: &String| identifier == "CheckMetadataHash";
+  Ok(match &metadata {
+    RuntimeMetadata::V14(md) => md
+      .extrinsic
+      .signed_extensions
+      .iter()
+      .any(|ext| is_check(&ext.identifier)),
+    RuntimeMetadata::V15(md) => md
+      .extrinsic
+      .signed_extensions
+      .iter()
+      .any(|ext| is_check(&ext.identifier)),
+    _ => false,
+  })
+}
+
+/// Calculate the RFC-78 metadata hash used by the `CheckMetadataHash` signed extension.
+///
+/// `metadata` is the SCALE encoded `RuntimeMetadataPrefixed` (V14 or V15).  The spec name,
+/// spec version and SS58 prefix are read from the `System` pallet constants.  The token
+/// `decimals` and `token_symbol` are from the chain's `system_properties`.
+///
+/// The runtime calculates the hash from the V15 metadata, so V14 metadata is converted first.
+pub fn calculate_metadata_hash(metadata: &[u8], decimals: u8, token_symbol: &str) -> Result<H256> {
+  let metadata = RuntimeMetadataPrefixed::decode(&mut &metadata[..])
+    .map_err(|e| Error::MetadataHash(format!("Invalid metadata: {e:?}")))?
+    .1;
+  let version = system_constant(&metadata, "Version")?;
+  // Only decode the start of `RuntimeVersion`: spec_name, impl_name, authoring_version, spec_version.
+  let (spec_name, _, _, spec_version) = <(String, String, u32, u32)>::decode(&mut &version[..])?;
+  let base58_prefix = u16::decode(&mut system_constant(&metadata, "SS58Prefix")?)?;
+  let metadata = match metadata {
+    RuntimeMetadata::V14(md) => RuntimeMetadata::V15(v14_to_v15(md)?),
+    md => md,
+  };
+  let digest = generate_metadata_digest(
+    &metadata,
+    ExtraInfo {
+      spec_version,
+      spec_name,
+      base58_prefix,
+      decimals,
+      token_symbol: token_symbol.into(),
+    },
+  )
+  .map_err(Error::MetadataHash)?;
+  Ok(H256(digest.hash()))
+}
+
+/// Convert V14 metadata to V15.
+///
+/// The extrinsic's address, call, signature and extra types are taken from the type
+/// parameters of the V14 extrinsic type.  V14 doesn't have runtime APIs or outer enums.
+fn v14_to_v15(md: RuntimeMetadataV14) -> Result<RuntimeMetadataV15> {
+  let extrinsic_ty = md
+    .types
+    .resolve(md.extrinsic.ty.id)
+    .ok_or_else(|| Error::MetadataHash("Missing extrinsic type".into()))?;
+  let type_param = |name: &str| {
+    extrinsic_ty
+      .type_params
+      .iter()
+      .find(|param| param.name == name)
+      .and_then(|param| param.ty)
+      .ok_or_else(|| Error::MetadataHash(format!("Missing extrinsic type parameter `{name}`")))
+  };
+  let call_ty = type_param("Call")?;
+  let extrinsic = ExtrinsicMetadata {
+    version: md.extrinsic.version,
+    address_ty: type_param("Address")?,
+    call_ty,
+    signature_ty: type_param("Signature")?,
+    extra_ty: type_param("Extra")?,
+    signed_extensions: md
+      .extrinsic
+      .signed_extensions
+      .into_iter()
+      .map(|ext| SignedExtensionMetadata {
+        identifier: ext.identifier,
+        ty: ext.ty,
+        additional_signed: ext.additional_signed,
+      })
+      .collect(),
+  };
+  let pallets = md
+    .pallets
+    .into_iter()
+    .map(|pallet| PalletMetadata {
+      name: pallet.name,
+      storage: pallet.storage,
+      calls: pallet.calls,
+      event: pallet.event,
+      constants: pallet.constants,
+      error: pallet.error,
+      index: pallet.index,
+      docs: Vec::new(),
+    })
+    .collect();
+  Ok(RuntimeMetadataV15 {
+    types: md.types,
+    pallets,
+    extrinsic,
+    ty: md.ty,
+    apis: Vec::new(),
+    // The outer enums are not used by the metadata hash.
+    outer_enums: OuterEnums {
+      call_enum_ty: call_ty,
+      event_enum_ty: call_ty,
+      error_enum_ty: call_ty,
+    },
+    custom: CustomMetadata {
+      map: Default::default(),
+    },
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use codec::Encode;
+
+  const METADATA: &[u8] = include_bytes!("../../../specs/polymesh_dev_spec_8000001.meta");
+
+  /// The metadata hash of spec 8000001 with 6 decimals and the "POLYX" token symbol.
+  ///
+  /// Regression value, calculated with this implementation.
+  const METADATA_HASH: &str = "0xd0e8643c367bd48351c82e7da2a44c1823e8ad8785442e37a991e3b072c94826";
+
+  fn metadata_hash() -> H256 {
+    METADATA_HASH.parse().expect("valid hash")
+  }
+
+  #[test]
+  fn test_calculate_metadata_hash() -> anyhow::Result<()> {
+    let metadata = METADATA;
+    let hash = calculate_metadata_hash(metadata, 6, "POLYX")?;
+    assert_eq!(hash, metadata_hash());
+    // The hash depends on all inputs.
+    assert_ne!(hash, calculate_metadata_hash(metadata, 6, "POLY")?);
+    assert_ne!(hash, calculate_metadata_hash(metadata, 12, "POLYX")?);
+    assert!(calculate_metadata_hash(&metadata[..100], 6, "POLYX").is_err());
+    Ok(())
+  }
+
+  #[test]
+  fn test_has_check_metadata_hash() -> anyhow::Result<()> {
+    assert!(has_check_metadata_hash(METADATA)?);
+    let v7 = include_bytes!("../../../specs/polymesh_dev_spec_7004000.meta");
+    assert!(!has_check_metadata_hash(v7)?);
+    Ok(())
+  }
+
+  #[test]
+  fn test_v14_to_v15() -> anyhow::Result<()> {
+    let v14 = match RuntimeMetadataPrefixed::decode(&mut &METADATA[..])?.1 {
+      RuntimeMetadata::V14(md) => md,
+      md => panic!("Expected V14 metadata, got V{}", md.version()),
+    };
+    let v15 = v14_to_v15(v14.clone())?;
+    assert_eq!(v15.pallets.len(), v14.pallets.len());
+    assert_eq!(v15.extrinsic.version, v14.extrinsic.version);
+    let type_path = |id: u32| {
+      v15
+        .types
+        .resolve(id)
+        .map(|ty| ty.path.segments.join("::"))
+        .unwrap_or_default()
+    };
+    assert!(type_path(v15.extrinsic.call_ty.id).ends_with("RuntimeCall"));
+    assert!(type_path(v15.extrinsic.address_ty.id).ends_with("MultiAddress"));
+    assert!(type_path(v15.extrinsic.signature_ty.id).ends_with("MultiSignature"));
+    let extensions: Vec<_> = v15
+      .extrinsic
+      .signed_extensions
+      .iter()
+      .map(|ext| ext.identifier.as_str())
+      .collect();
+    assert!(extensions.contains(&"CheckMetadataHash"));
+
+    // The converted V15 metadata has the same hash.
+    let encoded = RuntimeMetadataPrefixed::from(v15).encode();
+    assert_eq!(
+      calculate_metadata_hash(&encoded, 6, "POLYX")?,
+      metadata_hash()
+    );
+    Ok(())
+  }
+}
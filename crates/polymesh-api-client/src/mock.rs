@@ -0,0 +1,777 @@
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+use codec::{Decode, Encode};
+use frame_metadata::RuntimeMetadataPrefixed;
+use serde::Serialize;
+use serde_json::{json, Value};
+use sp_core::{hashing::blake2_256, Bytes, H256};
+use tokio::sync::mpsc;
+
+use async_trait::async_trait;
+
+use crate::*;
+
+/// The `RuntimeVersion` as SCALE encoded in the `System.Version` constant.
+#[derive(Decode)]
+struct EncodedRuntimeVersion {
+  spec_name: String,
+  impl_name: String,
+  authoring_version: u32,
+  spec_version: u32,
+  impl_version: u32,
+  _apis: Vec<([u8; 8], u32)>,
+  transaction_version: u32,
+}
+
+/// Get the `RuntimeVersion` from the `System.Version` constant in the chain metadata.
+fn metadata_runtime_version(metadata: &RuntimeMetadataPrefixed) -> Result<RuntimeVersion> {
+  let mut value = metadata::system_constant(&metadata.1, "Version")?;
+  let version = EncodedRuntimeVersion::decode(&mut value)?;
+  Ok(RuntimeVersion {
+    spec_name: version.spec_name,
+    impl_name: version.impl_name,
+    authoring_version: version.authoring_version,
+    spec_version: version.spec_version,
+    impl_version: version.impl_version,
+    transaction_version: version.transaction_version,
+    extra: Default::default(),
+  })
+}
+
+fn to_json<T: Serialize>(value: T) -> Result<Value> {
+  Ok(serde_json::to_value(value)?)
+}
+
+/// Get the JSON param at `idx`.  Missing and `null` params are `None`.
+fn param<T: serde::de::DeserializeOwned>(params: &[Value], idx: usize) -> Result<Option<T>> {
+  match params.get(idx) {
+    None | Some(Value::Null) => Ok(None),
+    Some(value) => Ok(Some(serde_json::from_value(value.clone())?)),
+  }
+}
+
+fn required_param<T: serde::de::DeserializeOwned>(params: &[Value], idx: usize) -> Result<T> {
+  param(params, idx)?.ok_or_else(|| Error::RpcClient(format!("Missing param {idx}")))
+}
+
+fn connection_lost() -> Error {
+  Error::ConnectionLost("Mock node disconnected".into())
+}
+
+fn method_not_found(method: &str) -> Error {
+  let err =
+    jsonrpsee::types::ErrorObject::owned(-32601, format!("Method not found: {method}"), None::<()>);
+  Error::Jsonrpsee(jsonrpsee::core::Error::Call(err))
+}
+
+#[derive(Clone)]
+struct MockBlock {
+  header: Header,
+  extrinsics: Vec<Encoded>,
+}
+
+impl MockBlock {
+  fn new(parent: Option<&MockBlock>, extrinsics: Vec<Encoded>) -> Self {
+    let (parent_hash, number) = match parent {
+      Some(parent) => (parent.header.hash(), parent.header.number + 1),
+      None => (BlockHash::zero(), 0),
+    };
+    Self {
+      header: Header {
+        parent_hash,
+        number,
+        state_root: BlockHash::zero(),
+        extrinsics_root: H256(extrinsics.using_encoded(blake2_256)),
+        digest: Default::default(),
+      },
+      extrinsics,
+    }
+  }
+
+  fn hash(&self) -> BlockHash {
+    self.header.hash()
+  }
+
+  fn to_json(&self) -> Result<Value> {
+    Ok(json!({
+      "block": {
+        "header": to_json(&self.header)?,
+        "extrinsics": to_json(&self.extrinsics)?,
+      },
+      "justifications": null,
+    }))
+  }
+}
+
+struct MockSubscriber {
+  method: String,
+  keys: Option<Vec<StorageKey>>,
+  sender: mpsc::UnboundedSender<Result<Value>>,
+}
+
+struct MockState {
+  runtime_version: RuntimeVersion,
+  metadata: Vec<u8>,
+  properties: SystemProperties,
+  storage: StorageSnapshot,
+  blocks: Vec<MockBlock>,
+  submitted: Vec<Encoded>,
+  responses: BTreeMap<String, Value>,
+  runtime_api_responses: BTreeMap<String, Vec<u8>>,
+  subscribers: Vec<MockSubscriber>,
+  connected: bool,
+  /// Drop the connection after handling a request for this method.
+  disconnect_on: Option<String>,
+}
+
+impl MockState {
+  fn best_block(&self) -> &MockBlock {
+    self.blocks.last().expect("Mock has a genesis block")
+  }
+
+  fn find_block(&self, hash: Option<BlockHash>) -> Option<&MockBlock> {
+    match hash {
+      Some(hash) => self.blocks.iter().find(|b| b.hash() == hash),
+      None => Some(self.best_block()),
+    }
+  }
+
+  /// Drop the connection.  Ends all subscriptions.
+  fn disconnect(&mut self) {
+    self.connected = false;
+    self.subscribers.clear();
+  }
+
+  /// Send a notification to the subscribers of `method`.
+  fn notify(&mut self, method: &str, notif: Value) {
+    self
+      .subscribers
+      .retain(|sub| sub.method != method || sub.sender.send(Ok(notif.clone())).is_ok());
+  }
+
+  fn storage_change_set(&self, keys: &[StorageKey]) -> Result<Value> {
+    let changes = keys
+      .iter()
+      .map(|key| (key.clone(), self.storage.get_storage_data_by_key(key)))
+      .collect();
+    to_json(StorageChangeSet {
+      block: self.best_block().hash(),
+      changes,
+    })
+  }
+
+  fn notify_storage(&mut self, changed: &StorageKey) -> Result<()> {
+    let mut notifs = Vec::new();
+    for (idx, sub) in self.subscribers.iter().enumerate() {
+      match &sub.keys {
+        Some(keys) if keys.iter().any(|key| key.0 == changed.0) => {
+          notifs.push((idx, self.storage_change_set(std::slice::from_ref(changed))?));
+        }
+        _ => (),
+      }
+    }
+    for (idx, notif) in notifs {
+      let _ = self.subscribers[idx].sender.send(Ok(notif));
+    }
+    Ok(())
+  }
+
+  /// Record a submitted extrinsic and include it in a new block.
+  fn submit_extrinsic(&mut self, xt: Bytes) -> Result<(TxHash, BlockHash)> {
+    let tx_hash = H256(blake2_256(&xt.0));
+    let xt = Encoded(xt.0);
+    self.submitted.push(xt.clone());
+    let block = MockBlock::new(Some(self.best_block()), vec![xt]);
+    let block_hash = block.hash();
+    let header = to_json(&block.header)?;
+    self.blocks.push(block);
+    self.notify("chain_subscribeNewHeads", header.clone());
+    self.notify("chain_subscribeFinalizedHeads", header);
+    Ok((tx_hash, block_hash))
+  }
+
+  fn request(&mut self, method: &str, params: &[Value]) -> Result<Value> {
+    if let Some(res) = self.responses.get(method) {
+      return Ok(res.clone());
+    }
+    match method {
+      "state_getRuntimeVersion" => to_json(&self.runtime_version),
+      "state_getMetadata" => to_json(Bytes(self.metadata.clone())),
+      "state_call" => {
+        let name: String = param(params, 0)?.unwrap_or_default();
+        match self.runtime_api_responses.get(&name) {
+          Some(data) => to_json(Bytes(data.clone())),
+          None => Err(method_not_found(&name)),
+        }
+      }
+      "system_properties" => to_json(&self.properties),
+      "system_health" => to_json(SystemHealth {
+        peers: 0,
+        is_syncing: false,
+        should_have_peers: false,
+      }),
+      "chain_getBlockHash" => {
+        let number: u32 = param(params, 0)?.unwrap_or_default();
+        to_json(self.blocks.get(number as usize).map(|b| b.hash()))
+      }
+      "chain_getFinalizedHead" => to_json(self.best_block().hash()),
+      "chain_getHeader" => to_json(self.find_block(param(params, 0)?).map(|b| &b.header)),
+      "chain_getBlock" => match self.find_block(param(params, 0)?) {
+        Some(block) => block.to_json(),
+        None => Ok(Value::Null),
+      },
+      "state_getStorage" => {
+        let key: StorageKey = param(params, 0)?.unwrap_or(StorageKey(vec![]));
+        to_json(self.storage.get_storage_data_by_key(&key))
+      }
+      "state_getKeysPaged" => {
+        let prefix: StorageKey = param(params, 0)?.unwrap_or(StorageKey(vec![]));
+        let count: usize = param(params, 1)?.unwrap_or_default();
+        let start_key: Option<StorageKey> = param(params, 2)?;
+        to_json(
+          self
+            .storage
+            .get_storage_keys_paged(&prefix, count, start_key.as_ref()),
+        )
+      }
+      "state_queryStorageAt" => {
+        let keys: Vec<StorageKey> = param(params, 0)?.unwrap_or_default();
+        Ok(Value::Array(vec![self.storage_change_set(&keys)?]))
+      }
+      "author_submitExtrinsic" => {
+        let xt: Bytes = required_param(params, 0)?;
+        let (tx_hash, _) = self.submit_extrinsic(xt)?;
+        to_json(tx_hash)
+      }
+      _ => Err(method_not_found(method)),
+    }
+  }
+
+  fn subscribe(&mut self, method: &str, params: &[Value]) -> Result<TransportSubscription> {
+    let (sender, mut receiver) = mpsc::unbounded_channel();
+    let mut keys = None;
+    match method {
+      "author_submitAndWatchExtrinsic" => {
+        let xt: Bytes = required_param(params, 0)?;
+        let (_, block_hash) = self.submit_extrinsic(xt)?;
+        for status in [
+          TransactionStatus::Ready,
+          TransactionStatus::InBlock(block_hash),
+          TransactionStatus::Finalized(block_hash),
+        ] {
+          let _ = sender.send(to_json(status));
+        }
+      }
+      "chain_subscribeNewHeads" | "chain_subscribeFinalizedHeads" => {
+        let _ = sender.send(to_json(&self.best_block().header));
+      }
+      "chain_subscribeRuntimeVersion" => {
+        let _ = sender.send(to_json(&self.runtime_version));
+      }
+      "state_subscribeStorage" => {
+        let sub_keys: Vec<StorageKey> = param(params, 0)?.unwrap_or_default();
+        let _ = sender.send(self.storage_change_set(&sub_keys));
+        keys = Some(sub_keys);
+      }
+      _ => return Err(method_not_found(method)),
+    }
+    // Transaction status updates end after the transaction is finalized.
+    if method != "author_submitAndWatchExtrinsic" {
+      self.subscribers.push(MockSubscriber {
+        method: method.into(),
+        keys,
+        sender,
+      });
+    }
+    let stream = async_stream::stream! {
+      while let Some(notif) = receiver.recv().await {
+        yield notif;
+      }
+    };
+    Ok(Box::pin(stream))
+  }
+}
+
+/// An in-process mock node for unit testing code that uses the [`Client`].
+///
+/// The mock serves the chain metadata (e.g. from a `specs/*.meta` file) and
+/// storage from an in-memory map.  Submitted extrinsics are recorded and each
+/// is included in a new block, but they are not executed, so the storage and
+/// events don't change unless the test sets them.
+///
+/// ```ignore
+/// let node = MockNode::from_metadata_file("specs/polymesh_dev_spec_7004000.meta")?;
+/// let client = node.client().await?;
+/// ```
+#[derive(Clone)]
+pub struct MockNode {
+  state: Arc<Mutex<MockState>>,
+}
+
+impl MockNode {
+  /// Create a mock node from the SCALE encoded chain metadata.
+  pub fn new(metadata: Vec<u8>) -> Result<Self> {
+    let md = RuntimeMetadataPrefixed::decode(&mut metadata.as_slice())?;
+    let runtime_version = metadata_runtime_version(&md)?;
+    let genesis = MockBlock::new(None, vec![]);
+    Ok(Self {
+      state: Arc::new(Mutex::new(MockState {
+        runtime_version,
+        metadata,
+        properties: SystemProperties {
+          ss58_format: 42,
+          token_decimals: 6,
+          token_symbol: "POLYX".into(),
+        },
+        storage: StorageSnapshot::new(genesis.hash(), StorageKey(vec![])),
+        blocks: vec![genesis],
+        submitted: Vec::new(),
+        responses: BTreeMap::new(),
+        runtime_api_responses: BTreeMap::new(),
+        subscribers: Vec::new(),
+        connected: true,
+        disconnect_on: None,
+      })),
+    })
+  }
+
+  /// Create a mock node from a chain metadata file (e.g. `specs/polymesh_dev_spec_7004000.meta`).
+  pub fn from_metadata_file(path: impl AsRef<std::path::Path>) -> Result<Self> {
+    Self::new(std::fs::read(path)?)
+  }
+
+  fn state(&self) -> std::sync::MutexGuard<'_, MockState> {
+    self.state.lock().expect("MockNode lock poisoned")
+  }
+
+  /// Create a client connected to this mock node.
+  pub async fn client(&self) -> Result<Client> {
+    Client::new_with_transport("mock://", self.clone()).await
+  }
+
+  pub fn runtime_version(&self) -> RuntimeVersion {
+    self.state().runtime_version.clone()
+  }
+
+  /// Upgrade the runtime to new chain metadata.  The runtime version is taken
+  /// from the metadata and runtime version subscribers are notified.
+  pub fn set_metadata(&self, metadata: Vec<u8>) -> Result<()> {
+    let md = RuntimeMetadataPrefixed::decode(&mut metadata.as_slice())?;
+    let version = metadata_runtime_version(&md)?;
+    self.state().metadata = metadata;
+    self.set_runtime_version(version)
+  }
+
+  /// Change the runtime version.  Runtime version subscribers are notified.
+  pub fn set_runtime_version(&self, version: RuntimeVersion) -> Result<()> {
+    let mut state = self.state();
+    let notif = to_json(&version)?;
+    state.runtime_version = version;
+    state.notify("chain_subscribeRuntimeVersion", notif);
+    Ok(())
+  }
+
+  pub fn set_system_properties(&self, properties: SystemProperties) {
+    self.state().properties = properties;
+  }
+
+  /// Set a raw storage value.  Storage subscribers are notified.
+  pub fn set_storage(&self, key: StorageKey, value: StorageData) -> Result<()> {
+    let mut state = self.state();
+    state.storage.insert(key.clone(), value);
+    state.notify_storage(&key)
+  }
+
+  /// Set a storage value.
+  pub fn set_storage_value<T: Encode>(&self, key: StorageKey, value: &T) -> Result<()> {
+    self.set_storage(key, StorageData(value.encode()))
+  }
+
+  /// Add the storage entries of a snapshot (see [`StorageSnapshot::load`]).
+  pub fn load_snapshot(&self, snapshot: &StorageSnapshot) {
+    let mut state = self.state();
+    for (key, value) in snapshot.entries() {
+      state.storage.insert(key, value);
+    }
+  }
+
+  pub fn get_storage(&self, key: &StorageKey) -> Option<StorageData> {
+    self.state().storage.get_storage_data_by_key(key)
+  }
+
+  /// Set the JSON result of a RPC `method`.  This overrides the built-in handlers.
+  pub fn set_response<T: Serialize>(&self, method: &str, result: T) -> Result<()> {
+    let result = to_json(result)?;
+    self.state().responses.insert(method.into(), result);
+    Ok(())
+  }
+
+  /// Set the SCALE encoded result of a runtime API method (e.g. `TransactionPaymentApi_query_info`)
+  /// called with `state_call`.
+  pub fn set_runtime_api_response(&self, method: &str, result: Vec<u8>) {
+    self
+      .state()
+      .runtime_api_responses
+      .insert(method.into(), result);
+  }
+
+  /// The extrinsics submitted to this mock node.
+  pub fn submitted_extrinsics(&self) -> Vec<Encoded> {
+    self.state().submitted.clone()
+  }
+
+  /// Clear the recorded extrinsics.
+  pub fn clear_submitted_extrinsics(&self) {
+    self.state().submitted.clear();
+  }
+
+  /// The hash of the best block.  Each submitted extrinsic is included in a new block.
+  pub fn best_block_hash(&self) -> BlockHash {
+    self.state().best_block().hash()
+  }
+
+  /// Simulate a lost connection.  All subscriptions end and requests fail until
+  /// the client reconnects (see [`RpcTransport::reconnect`]).
+  pub fn disconnect(&self) {
+    self.state().disconnect();
+  }
+
+  /// Drop the connection after handling the next request for `method`,
+  /// before the response is sent.
+  pub fn disconnect_on(&self, method: &str) {
+    self.state().disconnect_on = Some(method.into());
+  }
+
+  /// The number of open subscriptions for the RPC `method` (e.g. `chain_subscribeNewHeads`).
+  pub fn subscription_count(&self, method: &str) -> usize {
+    self
+      .state()
+      .subscribers
+      .iter()
+      .filter(|sub| sub.method == method && !sub.sender.is_closed())
+      .count()
+  }
+}
+
+#[async_trait]
+impl RpcTransport for MockNode {
+  async fn request(&self, method: &str, params: Vec<Value>) -> Result<Value> {
+    let mut state = self.state();
+    if !state.connected {
+      return Err(connection_lost());
+    }
+    let res = state.request(method, &params);
+    if state.disconnect_on.as_deref() == Some(method) {
+      state.disconnect_on = None;
+      state.disconnect();
+      return Err(connection_lost());
+    }
+    res
+  }
+
+  async fn subscribe(
+    &self,
+    subscribe_method: &str,
+    params: Vec<Value>,
+    _unsubscribe_method: &str,
+  ) -> Result<TransportSubscription> {
+    let mut state = self.state();
+    if !state.connected {
+      return Err(connection_lost());
+    }
+    state.subscribe(subscribe_method, &params)
+  }
+
+  fn is_connected(&self) -> bool {
+    self.state().connected
+  }
+
+  async fn reconnect(&self) -> Result<()> {
+    self.state().connected = true;
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::rpc::{RpcClient, RpcPool};
+  use jsonrpsee::rpc_params;
+  use std::time::Duration;
+
+  const SPEC_FILE: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/../../specs/polymesh_dev_spec_7004000.meta"
+  );
+
+  #[tokio::test]
+  async fn test_mock_node() -> anyhow::Result<()> {
+    let node = MockNode::from_metadata_file(SPEC_FILE)?;
+    let client = node.client().await?;
+    let version = client
+      .get_block_runtime_version(None)
+      .await?
+      .expect("RuntimeVersion");
+    assert_eq!(version.spec_version, 7004000);
+    assert_eq!(
+      client.get_transaction_version(),
+      version.transaction_version as i64
+    );
+    assert_eq!(client.get_genesis_hash(), node.best_block_hash());
+
+    // Storage.
+    let prefix = pallet_storage_prefix("Test");
+    let key = StorageKey([prefix.0.clone(), vec![1]].concat());
+    assert_eq!(
+      client.get_storage_by_key::<u32>(key.clone(), None).await?,
+      None
+    );
+    node.set_storage_value(key.clone(), &42u32)?;
+    assert_eq!(
+      client.get_storage_by_key::<u32>(key.clone(), None).await?,
+      Some(42)
+    );
+    let keys = client.get_all_storage_keys(&prefix, None).await?;
+    assert_eq!(keys.len(), 1);
+    let values = client.get_storage_data_by_keys(&keys, None).await?;
+    assert_eq!(
+      values[0].as_ref().map(|v| v.0.clone()),
+      Some(42u32.encode())
+    );
+
+    // Storage subscriptions.
+    let mut sub = client.subscribe_storage(vec![key.clone()]).await?;
+    let set = sub.next().await.expect("Initial value")?;
+    assert_eq!(set.changes.len(), 1);
+    node.set_storage_value(key.clone(), &7u32)?;
+    let set = sub.next().await.expect("Changed value")?;
+    assert_eq!(
+      set.changes[0].1.as_ref().map(|v| v.0.clone()),
+      Some(7u32.encode())
+    );
+
+    // Submitted extrinsics.
+    let xt = vec![4u8, 1, 2, 3];
+    let mut sub = client
+      .submit_and_watch(format!("0x{}", hex::encode(&xt)))
+      .await?;
+    assert_eq!(
+      sub.next().await.transpose()?,
+      Some(TransactionStatus::Ready)
+    );
+    let block_hash = node.best_block_hash();
+    assert_eq!(
+      sub.next().await.transpose()?,
+      Some(TransactionStatus::InBlock(block_hash))
+    );
+    let block = client.get_block(Some(block_hash)).await?.expect("Block");
+    assert_eq!(block.block_number(), 1);
+    assert_eq!(block.extrinsics()[0].0, xt);
+    assert_eq!(node.submitted_extrinsics().len(), 1);
+
+    // Unknown RPC methods.
+    assert!(client
+      .dry_run(format!("0x{}", hex::encode(&xt)), None)
+      .await
+      .is_err());
+    Ok(())
+  }
+
+  #[tokio::test]
+  async fn test_mock_runtime_upgrade() -> anyhow::Result<()> {
+    let node = MockNode::from_metadata_file(SPEC_FILE)?;
+    let client = node.client().await?;
+    assert_eq!(client.get_runtime_version().spec_version, 7004000);
+    // `Balances.transfer_with_memo` was removed in 8.0.
+    client.check_call("Balances.transfer_with_memo", &[5, 1])?;
+    assert!(client.check_call("Balances.transfer", &[5, 1]).is_err());
+
+    node.set_metadata(std::fs::read(SPEC_FILE.replace("7004000", "8000001"))?)?;
+    // Wait for the client to reload the metadata.
+    for _ in 0..100 {
+      if client.get_runtime_version().spec_version == 8000001 {
+        break;
+      }
+      tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    }
+    assert_eq!(client.get_runtime_version().spec_version, 8000001);
+    assert!(matches!(
+      client.check_call("Balances.transfer_with_memo", &[5, 1]),
+      Err(Error::IncompatibleCall(_))
+    ));
+    client.check_call("System.remark", &[0, 0])?;
+    Ok(())
+  }
+
+  async fn wait_for(check: impl Fn() -> bool) -> bool {
+    for _ in 0..300 {
+      if check() {
+        return true;
+      }
+      tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    check()
+  }
+
+  #[tokio::test]
+  async fn test_mock_runtime_watcher() -> anyhow::Result<()> {
+    const METHOD: &str = "chain_subscribeRuntimeVersion";
+    let node = MockNode::from_metadata_file(SPEC_FILE)?;
+    let client = node.client().await?;
+    assert!(wait_for(|| node.subscription_count(METHOD) == 1).await);
+
+    // The watcher resubscribes after the connection is lost.
+    node.disconnect();
+    RpcTransport::reconnect(&node).await?;
+    assert!(wait_for(|| node.subscription_count(METHOD) == 1).await);
+    node.set_metadata(std::fs::read(SPEC_FILE.replace("7004000", "8000001"))?)?;
+    assert!(wait_for(|| client.get_runtime_version().spec_version == 8000001).await);
+
+    // The watcher stops when the client is dropped.
+    drop(client);
+    assert!(wait_for(|| node.subscription_count(METHOD) == 0).await);
+    Ok(())
+  }
+
+  #[cfg(feature = "metadata_hash")]
+  #[tokio::test]
+  async fn test_mock_metadata_hash() -> anyhow::Result<()> {
+    // The runtime doesn't have the `CheckMetadataHash` signed extension.
+    let node = MockNode::from_metadata_file(SPEC_FILE)?;
+    assert_eq!(node.client().await?.get_metadata_hash(), None);
+
+    let metadata = std::fs::read(SPEC_FILE.replace("7004000", "8000001"))?;
+    let node = MockNode::new(metadata.clone())?;
+    assert_eq!(
+      node.client().await?.get_metadata_hash(),
+      Some(calculate_metadata_hash(&metadata, 6, "POLYX")?)
+    );
+
+    // Chains with multiple tokens can be used, but without the metadata hash.
+    node.set_response(
+      "system_properties",
+      json!({
+        "ss58Format": 42,
+        "tokenDecimals": [6, 12],
+        "tokenSymbol": ["POLYX", "TEST"],
+      }),
+    )?;
+    assert_eq!(node.client().await?.get_metadata_hash(), None);
+    Ok(())
+  }
+
+  fn reconnect_config() -> ReconnectConfig {
+    ReconnectConfig {
+      initial_delay: Duration::from_millis(1),
+      max_delay: Duration::from_millis(10),
+      max_attempts: Some(3),
+    }
+  }
+
+  #[tokio::test]
+  async fn test_mock_reconnect() -> anyhow::Result<()> {
+    let node = MockNode::from_metadata_file(SPEC_FILE)?;
+    let rpc = RpcClient::new_with_transport_reconnect("mock://", node.clone(), reconnect_config());
+    let mut heads = rpc
+      .subscribe::<Header>(
+        "chain_subscribeNewHeads",
+        rpc_params!(),
+        "chain_unsubscribeNewHeads",
+      )
+      .await?;
+    assert_eq!(heads.next().await.transpose()?.map(|h| h.number), Some(0));
+
+    // The subscription is re-issued after the connection is lost.
+    node.disconnect();
+    assert!(!rpc.is_connected());
+    assert_eq!(heads.next().await.transpose()?.map(|h| h.number), Some(0));
+    assert!(rpc.is_connected());
+    assert_eq!(node.subscription_count("chain_subscribeNewHeads"), 1);
+
+    // Idempotent requests are retried after reconnecting.
+    node.disconnect_on("system_health");
+    let _: SystemHealth = rpc.request("system_health", rpc_params!()).await?;
+
+    // Submitting a transaction is not retried, the node might have processed it.
+    node.disconnect_on("author_submitExtrinsic");
+    let res = rpc
+      .request::<TxHash>("author_submitExtrinsic", rpc_params!("0x04010203"))
+      .await;
+    assert!(matches!(res, Err(Error::ConnectionLost(_))), "{res:?}");
+    assert_eq!(node.submitted_extrinsics().len(), 1);
+
+    // The stream continues after the connection was lost again.
+    assert_eq!(heads.next().await.transpose()?.map(|h| h.number), Some(1));
+    let _: TxHash = rpc
+      .request("author_submitExtrinsic", rpc_params!("0x04040506"))
+      .await?;
+    assert_eq!(heads.next().await.transpose()?.map(|h| h.number), Some(2));
+
+    // Transaction status subscriptions are not re-issued.
+    let mut status = rpc
+      .subscribe::<TransactionStatus>(
+        "author_submitAndWatchExtrinsic",
+        rpc_params!("0x04070809"),
+        "author_unwatchExtrinsic",
+      )
+      .await?;
+    node.disconnect();
+    while status.next().await.is_some() {}
+    assert_eq!(node.submitted_extrinsics().len(), 3);
+    Ok(())
+  }
+
+  #[tokio::test]
+  async fn test_mock_failover() -> anyhow::Result<()> {
+    let node_a = MockNode::from_metadata_file(SPEC_FILE)?;
+    let node_b = MockNode::from_metadata_file(SPEC_FILE)?;
+    let config = EndpointsConfig {
+      round_robin: true,
+      health_check_interval: None,
+      resubscribe: reconnect_config(),
+    };
+    let pool = RpcPool::from_clients(
+      vec![
+        RpcClient::new_with_transport("mock://a", node_a.clone()),
+        RpcClient::new_with_transport("mock://b", node_b.clone()),
+      ],
+      config,
+    )
+    .await?;
+    assert_eq!(pool.active_url(), "mock://a");
+
+    // Node `b` is behind and doesn't have block 1.  Round-robin reads still find it.
+    let _: TxHash = pool
+      .request("author_submitExtrinsic", rpc_params!("0x04010203"))
+      .await?;
+    let hash = node_a.best_block_hash();
+    for _ in 0..4 {
+      let header: Option<Header> = pool
+        .read_request("chain_getHeader", rpc_params!(hash))
+        .await?;
+      assert_eq!(header.map(|h| h.number), Some(1));
+    }
+
+    let mut heads = pool
+      .subscribe::<Header>(
+        "chain_subscribeNewHeads",
+        rpc_params!(),
+        "chain_unsubscribeNewHeads",
+      )
+      .await?;
+    assert_eq!(heads.next().await.transpose()?.map(|h| h.number), Some(1));
+
+    // Node `a` goes down.  The subscription and requests move to node `b`.
+    node_a.disconnect();
+    assert_eq!(heads.next().await.transpose()?.map(|h| h.number), Some(0));
+    assert_eq!(pool.active_url(), "mock://b");
+    assert_eq!(node_b.subscription_count("chain_subscribeNewHeads"), 1);
+    let _: TxHash = pool
+      .request("author_submitExtrinsic", rpc_params!("0x04040506"))
+      .await?;
+    assert_eq!(node_b.submitted_extrinsics().len(), 1);
+    assert_eq!(heads.next().await.transpose()?.map(|h| h.number), Some(1));
+    Ok(())
+  }
+}
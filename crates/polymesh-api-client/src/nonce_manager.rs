@@ -0,0 +1,262 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::{Arc, Mutex};
+
+use sp_runtime::MultiSignature;
+
+use async_trait::async_trait;
+
+use crate::*;
+
+/// Nonce state of one account.
+#[derive(Debug, Default)]
+struct AccountNonces {
+  /// The next unused nonce.
+  next: u32,
+  /// Nonces below `next` that were given back and can be reused.
+  released: BTreeSet<u32>,
+  /// Reconcile with the on-chain nonce before the next reservation.
+  resync: bool,
+}
+
+impl AccountNonces {
+  /// Reconcile with the next nonce from the chain.
+  fn sync(&mut self, chain_nonce: u32) {
+    // Nonces below the on-chain nonce have been used.
+    self.released.retain(|nonce| *nonce >= chain_nonce);
+    if self.next < chain_nonce {
+      // Transactions from this account were submitted elsewhere.
+      self.next = chain_nonce;
+    }
+    self.resync = false;
+  }
+
+  /// The nonce that the next reservation will return.
+  fn peek(&self) -> u32 {
+    self.released.first().copied().unwrap_or(self.next)
+  }
+
+  /// Reserve a nonce.  Released nonces are reused first to fill gaps.
+  fn reserve(&mut self) -> u32 {
+    match self.released.pop_first() {
+      Some(nonce) => nonce,
+      None => {
+        let nonce = self.next;
+        self.next += 1;
+        nonce
+      }
+    }
+  }
+
+  /// Give back a reserved nonce.
+  fn release(&mut self, nonce: u32) {
+    if nonce + 1 == self.next {
+      self.next = nonce;
+      // Also drop any released nonces from the end.
+      while let Some(last) = self.released.last().copied() {
+        if last + 1 != self.next {
+          break;
+        }
+        self.released.pop_last();
+        self.next = last;
+      }
+    } else if nonce < self.next {
+      self.released.insert(nonce);
+    }
+    // The transaction might still have been included, check the chain next time.
+    self.resync = true;
+  }
+}
+
+/// Tracks the next nonce of accounts locally, so many transactions can be
+/// signed and submitted from the same account without waiting for blocks.
+///
+/// Nonces of rejected, invalid or dropped transactions are reused by the
+/// next transactions.  The local state is reconciled with the on-chain nonce
+/// when an account is first used and after a nonce is given back.
+#[derive(Clone)]
+pub struct NonceManager<Api: ChainApi> {
+  api: Api,
+  accounts: Arc<Mutex<BTreeMap<AccountId, AccountNonces>>>,
+}
+
+impl<Api: ChainApi + Send + Sync + 'static> NonceManager<Api> {
+  pub fn new(api: &Api) -> Self {
+    Self {
+      api: api.clone(),
+      accounts: Default::default(),
+    }
+  }
+
+  /// Wrap `signer` to use this nonce manager.
+  pub fn signer<S: Signer>(&self, signer: S) -> ManagedSigner<S, Api> {
+    ManagedSigner {
+      account: signer.account(),
+      signer: Arc::new(signer),
+      manager: self.clone(),
+    }
+  }
+
+  fn needs_sync(&self, account: &AccountId) -> bool {
+    let accounts = self.accounts.lock().expect("Nonce lock poisoned");
+    accounts.get(account).is_none_or(|nonces| nonces.resync)
+  }
+
+  /// Reserve the next nonce for `account`.
+  pub async fn next_nonce(&self, account: AccountId) -> Result<u32> {
+    self.with_nonces(account, AccountNonces::reserve).await
+  }
+
+  /// Get the next nonce for `account`, without reserving it.
+  pub async fn peek_nonce(&self, account: AccountId) -> Result<u32> {
+    self.with_nonces(account, |nonces| nonces.peek()).await
+  }
+
+  /// Call `f` with the nonce state of `account`, after syncing it with the chain if needed.
+  async fn with_nonces<F>(&self, account: AccountId, f: F) -> Result<u32>
+  where
+    F: FnOnce(&mut AccountNonces) -> u32,
+  {
+    let chain_nonce = if self.needs_sync(&account) {
+      Some(self.api.get_nonce(account).await?)
+    } else {
+      None
+    };
+    let mut accounts = self.accounts.lock().expect("Nonce lock poisoned");
+    let nonces = accounts.entry(account).or_default();
+    if let Some(chain_nonce) = chain_nonce {
+      nonces.sync(chain_nonce);
+    }
+    Ok(f(nonces))
+  }
+
+  /// Give back an unused `nonce` of `account`.
+  pub fn release(&self, account: AccountId, nonce: u32) {
+    let mut accounts = self.accounts.lock().expect("Nonce lock poisoned");
+    if let Some(nonces) = accounts.get_mut(&account) {
+      nonces.release(nonce);
+    }
+  }
+
+  /// Forget the local nonce of `account` and reload it from the chain on next use.
+  pub fn reset(&self, account: &AccountId) {
+    let mut accounts = self.accounts.lock().expect("Nonce lock poisoned");
+    accounts.remove(account);
+  }
+}
+
+impl<Api: ChainApi + Send + Sync + 'static> NonceRollback for NonceManager<Api> {
+  fn rollback(&self, account: AccountId, nonce: u32) {
+    self.release(account, nonce);
+  }
+}
+
+/// A signer using a [`NonceManager`] to manage its nonce.
+///
+/// Cloned signers share the same nonce manager, so they can be used to submit
+/// transactions concurrently.  The wrapped signer isn't locked.
+pub struct ManagedSigner<S, Api: ChainApi> {
+  signer: Arc<S>,
+  account: AccountId,
+  manager: NonceManager<Api>,
+}
+
+impl<S, Api: ChainApi> Clone for ManagedSigner<S, Api> {
+  fn clone(&self) -> Self {
+    Self {
+      signer: self.signer.clone(),
+      account: self.account,
+      manager: self.manager.clone(),
+    }
+  }
+}
+
+impl<S, Api: ChainApi> ManagedSigner<S, Api> {
+  pub fn inner(&self) -> &S {
+    &self.signer
+  }
+
+  pub fn manager(&self) -> &NonceManager<Api> {
+    &self.manager
+  }
+}
+
+#[async_trait]
+impl<S: Signer, Api: ChainApi + Send + Sync + 'static> Signer for ManagedSigner<S, Api> {
+  fn account(&self) -> AccountId {
+    self.account
+  }
+
+  async fn nonce(&self) -> Option<u32> {
+    match self.manager.peek_nonce(self.account).await {
+      Ok(nonce) => Some(nonce),
+      Err(err) => {
+        log::warn!("Failed to get nonce for {}: {err:?}", self.account);
+        None
+      }
+    }
+  }
+
+  async fn reserve_nonce(&self) -> Option<u32> {
+    match self.manager.next_nonce(self.account).await {
+      Ok(nonce) => Some(nonce),
+      Err(err) => {
+        log::warn!("Failed to reserve nonce for {}: {err:?}", self.account);
+        None
+      }
+    }
+  }
+
+  async fn set_nonce(&mut self, _nonce: u32) {
+    // The nonce was reserved by `reserve_nonce()`.
+  }
+
+  fn nonce_rollback(&self) -> Option<Arc<dyn NonceRollback>> {
+    Some(Arc::new(self.manager.clone()))
+  }
+
+  async fn sign(&self, msg: &[u8]) -> Result<MultiSignature> {
+    self.signer.sign(msg).await
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_account_nonces() {
+    let mut nonces = AccountNonces::default();
+    nonces.sync(5);
+    // Peeking doesn't reserve the nonce.
+    assert_eq!(nonces.peek(), 5);
+    assert_eq!(nonces.peek(), 5);
+    assert_eq!(nonces.reserve(), 5);
+    assert_eq!(nonces.reserve(), 6);
+    assert_eq!(nonces.reserve(), 7);
+    assert_eq!(nonces.reserve(), 8);
+
+    // Gaps are filled first.
+    nonces.release(6);
+    assert!(nonces.resync);
+    assert_eq!(nonces.peek(), 6);
+    assert_eq!(nonces.reserve(), 6);
+    assert_eq!(nonces.reserve(), 9);
+
+    // Releasing the last nonces moves `next` back.
+    nonces.release(7);
+    nonces.release(9);
+    nonces.release(8);
+    assert_eq!(nonces.next, 7);
+    assert!(nonces.released.is_empty());
+
+    // Released nonces that were used on-chain are dropped.
+    nonces.release(5);
+    nonces.sync(6);
+    assert!(nonces.released.is_empty());
+    assert_eq!(nonces.reserve(), 7);
+
+    // Catch up with transactions submitted elsewhere.
+    nonces.sync(20);
+    assert_eq!(nonces.reserve(), 20);
+  }
+}
@@ -0,0 +1,215 @@
+use std::time::Duration;
+
+use hyper::{
+  client::HttpConnector,
+  header::{HeaderName, HeaderValue, CONTENT_TYPE},
+  Body, Client as HttpClient, Method, Request, Uri,
+};
+use hyper_rustls::{HttpsConnector, HttpsConnectorBuilder};
+
+use serde::{Deserialize, Serialize};
+
+use sp_runtime::MultiSignature;
+
+use async_trait::async_trait;
+
+use crate::*;
+
+/// Request posted to the signing service.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RemoteSignRequest {
+  /// The account that needs to sign the payload.
+  pub account: AccountId,
+  /// The encoded `SignedPayload` to sign.
+  pub payload: sp_core::Bytes,
+}
+
+/// Response from the signing service.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RemoteSignResponse {
+  pub signature: MultiSignature,
+}
+
+/// A signer that uses a remote signing service (KMS, vault, etc.) over HTTP.
+///
+/// The `SignedPayload` bytes are posted as a JSON [`RemoteSignRequest`] to the
+/// signing endpoint, which returns a [`RemoteSignResponse`].  The returned signature
+/// is verified against the signer's account before it is used.
+#[derive(Clone)]
+pub struct RemoteSigner {
+  account: AccountId,
+  url: Uri,
+  headers: Vec<(HeaderName, HeaderValue)>,
+  timeout: Duration,
+  client: HttpClient<HttpsConnector<HttpConnector>>,
+  pub nonce: u32,
+}
+
+impl RemoteSigner {
+  /// Sign for `account` with the signing service at `url`.
+  pub fn new(account: AccountId, url: &str) -> Result<Self> {
+    let connector = HttpsConnectorBuilder::new()
+      .with_native_roots()
+      .https_or_http()
+      .enable_http1()
+      .build();
+    Ok(Self {
+      account,
+      url: url.parse()?,
+      headers: Vec::new(),
+      timeout: Duration::from_secs(30),
+      client: HttpClient::builder().build(connector),
+      nonce: 0,
+    })
+  }
+
+  /// Add a header to the signing requests.  For example an `Authorization` header.
+  pub fn header(mut self, name: &str, value: &str) -> Result<Self> {
+    let name = HeaderName::from_bytes(name.as_bytes()).map_err(http::Error::from)?;
+    let value = HeaderValue::from_str(value).map_err(http::Error::from)?;
+    self.headers.push((name, value));
+    Ok(self)
+  }
+
+  /// Timeout for signing requests.  The default is 30 seconds.
+  pub fn timeout(mut self, timeout: Duration) -> Self {
+    self.timeout = timeout;
+    self
+  }
+
+  async fn request(&self, msg: &[u8]) -> Result<MultiSignature> {
+    let body = serde_json::to_vec(&RemoteSignRequest {
+      account: self.account,
+      payload: msg.to_vec().into(),
+    })?;
+    let mut req = Request::builder()
+      .method(Method::POST)
+      .uri(self.url.clone())
+      .header(CONTENT_TYPE, "application/json");
+    for (name, value) in &self.headers {
+      req = req.header(name, value);
+    }
+    let req = req.body(Body::from(body))?;
+
+    let res = self
+      .client
+      .request(req)
+      .await
+      .map_err(|e| Error::RemoteSigner(format!("Request failed: {e}")))?;
+    let status = res.status();
+    let body = hyper::body::to_bytes(res.into_body())
+      .await
+      .map_err(|e| Error::RemoteSigner(format!("Failed to read response: {e}")))?;
+    if !status.is_success() {
+      return Err(Error::RemoteSigner(format!(
+        "Signing service returned {status}: {}",
+        String::from_utf8_lossy(&body)
+      )));
+    }
+    let res: RemoteSignResponse = serde_json::from_slice(&body)?;
+    Ok(res.signature)
+  }
+}
+
+#[async_trait]
+impl Signer for RemoteSigner {
+  fn account(&self) -> AccountId {
+    self.account
+  }
+
+  async fn nonce(&self) -> Option<u32> {
+    if self.nonce > 0 {
+      Some(self.nonce)
+    } else {
+      None
+    }
+  }
+
+  async fn set_nonce(&mut self, nonce: u32) {
+    self.nonce = nonce;
+  }
+
+  async fn sign(&self, msg: &[u8]) -> Result<MultiSignature> {
+    let sig = tokio::time::timeout(self.timeout, self.request(msg))
+      .await
+      .map_err(|_| Error::RemoteSigner("Signing request timed out".into()))??;
+    // Don't trust the signing service.
+    if !verify_signature(&self.account, &sig, msg) {
+      return Err(Error::RemoteSigner(format!(
+        "Invalid signature for account {}",
+        self.account
+      )));
+    }
+    Ok(sig)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::convert::Infallible;
+  use std::net::SocketAddr;
+
+  use hyper::service::{make_service_fn, service_fn};
+  use hyper::{Response, Server, StatusCode};
+  use sp_core::sr25519;
+
+  /// Mock signing service that signs with `key`.
+  async fn mock_service(key: sr25519::Pair, token: &'static str) -> SocketAddr {
+    let make_svc = make_service_fn(move |_| {
+      let key = key.clone();
+      async move {
+        Ok::<_, Infallible>(service_fn(move |req: Request<Body>| {
+          let key = key.clone();
+          async move {
+            let auth = req.headers().get("authorization");
+            if auth.map(|v| v.as_bytes()) != Some(token.as_bytes()) {
+              let mut res = Response::new(Body::from("Unauthorized"));
+              *res.status_mut() = StatusCode::UNAUTHORIZED;
+              return Ok::<_, Infallible>(res);
+            }
+            let body = hyper::body::to_bytes(req.into_body()).await.unwrap();
+            let req: RemoteSignRequest = serde_json::from_slice(&body).unwrap();
+            let signature = <sr25519::Pair as KeypairSigner>::sign(&key, &req.payload);
+            let res = serde_json::to_vec(&RemoteSignResponse { signature }).unwrap();
+            Ok(Response::new(Body::from(res)))
+          }
+        }))
+      }
+    });
+    let server = Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0))).serve(make_svc);
+    let addr = server.local_addr();
+    tokio::spawn(server);
+    addr
+  }
+
+  #[tokio::test]
+  async fn test_remote_signer() -> anyhow::Result<()> {
+    let alice = <sr25519::Pair as KeypairSigner>::from_string("//Alice", None)?;
+    let bob = <sr25519::Pair as KeypairSigner>::from_string("//Bob", None)?;
+    let addr = mock_service(alice.clone(), "Bearer secret").await;
+    let url = format!("http://{addr}/sign");
+    let msg = b"signed payload";
+
+    let signer = RemoteSigner::new(KeypairSigner::account(&alice), &url)?
+      .header("Authorization", "Bearer secret")?;
+    let sig = signer.sign(msg).await?;
+    assert!(alice.verify(&sig, msg)?);
+
+    // The signing service signs with the wrong key.
+    let signer = RemoteSigner::new(KeypairSigner::account(&bob), &url)?
+      .header("Authorization", "Bearer secret")?;
+    assert!(matches!(
+      signer.sign(msg).await,
+      Err(Error::RemoteSigner(_))
+    ));
+
+    // Missing authorization.
+    let signer = RemoteSigner::new(KeypairSigner::account(&alice), &url)?;
+    assert!(matches!(
+      signer.sign(msg).await,
+      Err(Error::RemoteSigner(_))
+    ));
+    Ok(())
+  }
+}
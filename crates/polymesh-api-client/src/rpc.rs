@@ -1,5 +1,22 @@
+use core::pin::Pin;
+use core::task::{Context, Poll};
+
+use std::sync::{
+  atomic::{AtomicBool, AtomicUsize, Ordering},
+  Arc, RwLock,
+};
+
+#[cfg(not(target_arch = "wasm32"))]
+use core::time::Duration;
+
+use futures_core::stream::Stream;
+
+use async_trait::async_trait;
+
+#[cfg(feature = "serde")]
+use jsonrpsee::core::traits::ToRpcParams;
 use jsonrpsee::core::{
-  client::{BatchResponse, ClientT, Subscription, SubscriptionClientT},
+  client::{BatchResponse, ClientT, SubscriptionClientT},
   params::{ArrayParams, BatchRequestBuilder},
 };
 #[cfg(target_arch = "wasm32")]
@@ -14,9 +31,185 @@ use sp_std::prelude::*;
 
 #[cfg(feature = "serde")]
 use serde::de::DeserializeOwned;
+use serde_json::Value;
 
 use crate::error::*;
 
+/// RPC methods that must not be retried or re-issued after a reconnect,
+/// since the node might have already processed them.
+const NON_IDEMPOTENT_METHODS: &[&str] = &[
+  "author_submitExtrinsic",
+  "author_submitAndWatchExtrinsic",
+  "author_insertKey",
+  "author_rotateKeys",
+];
+
+fn is_idempotent(method: &str) -> bool {
+  !NON_IDEMPOTENT_METHODS.contains(&method)
+}
+
+/// Reconnect settings for websocket connections.
+///
+/// The delay between reconnect attempts starts at `initial_delay` and is doubled
+/// after each failed attempt, up to `max_delay`.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Clone, Debug)]
+pub struct ReconnectConfig {
+  /// Delay before the first reconnect attempt.
+  pub initial_delay: Duration,
+  /// Maximum delay between reconnect attempts.
+  pub max_delay: Duration,
+  /// Give up after this many failed attempts.  `None` will retry forever.
+  pub max_attempts: Option<usize>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Default for ReconnectConfig {
+  fn default() -> Self {
+    Self {
+      initial_delay: Duration::from_millis(500),
+      max_delay: Duration::from_secs(30),
+      max_attempts: None,
+    }
+  }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl ReconnectConfig {
+  /// The delay to wait before reconnect `attempt` (starting from 0).
+  pub fn delay(&self, attempt: usize) -> Duration {
+    let factor = 1u32.checked_shl(attempt as u32).unwrap_or(u32::MAX);
+    self
+      .initial_delay
+      .checked_mul(factor)
+      .unwrap_or(self.max_delay)
+      .min(self.max_delay)
+  }
+}
+
+/// A stream of JSON notifications from a [`RpcTransport`] subscription.
+pub type TransportSubscription = Pin<Box<dyn Stream<Item = Result<Value>> + Send>>;
+
+/// A custom transport for the RPC client.
+///
+/// Allows using the client with a backend other than a websocket/http connection
+/// to a node.  For example the in-process `MockNode` used for unit tests.
+#[async_trait]
+pub trait RpcTransport: Send + Sync + 'static {
+  /// Send a request and return the JSON result.
+  async fn request(&self, method: &str, params: Vec<Value>) -> Result<Value>;
+
+  /// Subscribe and return the stream of JSON notifications.
+  async fn subscribe(
+    &self,
+    subscribe_method: &str,
+    params: Vec<Value>,
+    unsubscribe_method: &str,
+  ) -> Result<TransportSubscription>;
+
+  /// Returns `false` if the connection to the backend has been lost.
+  fn is_connected(&self) -> bool {
+    true
+  }
+
+  /// Re-establish the connection to the backend after it was lost.
+  ///
+  /// Only called when the `RpcClient` was created with a [`ReconnectConfig`].
+  async fn reconnect(&self) -> Result<()> {
+    Err(Error::ConnectionLost(
+      "The transport doesn't support reconnecting".into(),
+    ))
+  }
+}
+
+/// Convert jsonrpsee params to a list of JSON values.
+#[cfg(feature = "serde")]
+fn params_to_json(params: impl ToRpcParams) -> Result<Vec<Value>> {
+  match params.to_rpc_params()? {
+    Some(raw) => Ok(serde_json::from_str(raw.get())?),
+    None => Ok(Vec::new()),
+  }
+}
+
+#[derive(Clone)]
+struct TransportClient(Arc<dyn RpcTransport>);
+
+impl core::fmt::Debug for TransportClient {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    f.debug_tuple("TransportClient").finish()
+  }
+}
+
+#[cfg(feature = "serde")]
+impl TransportClient {
+  async fn subscribe<Notif>(
+    &self,
+    subscribe_method: &str,
+    params: ArrayParams,
+    unsubscribe_method: &str,
+  ) -> Result<Subscription<Notif>>
+  where
+    Notif: DeserializeOwned + Send + 'static,
+  {
+    let mut sub = self
+      .0
+      .subscribe(
+        subscribe_method,
+        params_to_json(params)?,
+        unsubscribe_method,
+      )
+      .await?;
+    let stream = async_stream::stream! {
+      while let Some(res) = core::future::poll_fn(|cx| sub.as_mut().poll_next(cx)).await {
+        yield res.and_then(|notif| Ok(serde_json::from_value(notif)?));
+      }
+    };
+    Ok(Subscription {
+      stream: Box::pin(stream),
+    })
+  }
+
+  async fn request<R>(&self, method: &str, params: ArrayParams) -> Result<R>
+  where
+    R: DeserializeOwned,
+  {
+    let res = self.0.request(method, params_to_json(params)?).await?;
+    Ok(serde_json::from_value(res)?)
+  }
+
+  /// The transport doesn't support batches, send the requests one at a time.
+  async fn batch_request<'a, R>(
+    &self,
+    batch: BatchRequestBuilder<'a>,
+  ) -> Result<BatchResponse<'a, R>>
+  where
+    R: DeserializeOwned + Default + Clone + alloc::fmt::Debug + 'a,
+  {
+    let mut responses = Vec::new();
+    let mut failed = 0;
+    for (method, params) in batch.iter() {
+      let params = match params {
+        Some(raw) => serde_json::from_str(raw.get())?,
+        None => Vec::new(),
+      };
+      let res = match self.0.request(method, params).await {
+        Ok(res) => Ok(serde_json::from_value(res)?),
+        Err(Error::Jsonrpsee(jsonrpsee::core::Error::Call(err))) => Err(err),
+        Err(err) => return Err(err),
+      };
+      if res.is_err() {
+        failed += 1;
+      }
+      responses.push(res);
+    }
+    Ok(BatchResponse::new(
+      responses.len() - failed,
+      responses,
+      failed,
+    ))
+  }
+}
+
 #[derive(Debug)]
 enum InnerRpcClient {
   #[cfg(not(target_arch = "wasm32"))]
@@ -25,15 +218,11 @@ enum InnerRpcClient {
   Http(HttpClient),
   #[cfg(target_arch = "wasm32")]
   Wasm(WasmClient),
+  Transport(TransportClient),
 }
 
-#[derive(Debug)]
-pub struct RpcClient {
-  client: InnerRpcClient,
-}
-
-impl RpcClient {
-  pub async fn new(url: &str) -> Result<Self> {
+impl InnerRpcClient {
+  async fn new(url: &str) -> Result<Self> {
     #[cfg(not(target_arch = "wasm32"))]
     {
       if url.starts_with("http") {
@@ -78,9 +267,7 @@ impl RpcClient {
       .max_request_size(1024 * 1024 * 1024)
       .build(&url)
       .await?;
-    Ok(Self {
-      client: InnerRpcClient::Ws(client),
-    })
+    Ok(Self::Ws(client))
   }
 
   #[cfg(not(target_arch = "wasm32"))]
@@ -88,80 +275,1040 @@ impl RpcClient {
     let client = HttpClientBuilder::default()
       .max_request_size(1024 * 1024 * 1024)
       .build(&url)?;
-    Ok(Self {
-      client: InnerRpcClient::Http(client),
-    })
+    Ok(Self::Http(client))
   }
 
   #[cfg(target_arch = "wasm32")]
   async fn new_wasm(url: &str) -> Result<Self> {
     let client = WasmClientBuilder::default().build(&url).await?;
-    Ok(Self {
-      client: InnerRpcClient::Wasm(client),
-    })
+    Ok(Self::Wasm(client))
+  }
+
+  /// Create a new connection to replace this one.
+  #[cfg(not(target_arch = "wasm32"))]
+  async fn reconnect(&self, url: &str) -> Result<Self> {
+    match self {
+      Self::Transport(transport) => {
+        transport.0.reconnect().await?;
+        Ok(Self::Transport(transport.clone()))
+      }
+      _ => Self::new(url).await,
+    }
+  }
+
+  /// Returns `false` if the connection to the node has been lost.
+  fn is_connected(&self) -> bool {
+    match self {
+      #[cfg(not(target_arch = "wasm32"))]
+      Self::Ws(ws) => ws.is_connected(),
+      #[cfg(not(target_arch = "wasm32"))]
+      Self::Http(_) => true,
+      #[cfg(target_arch = "wasm32")]
+      Self::Wasm(client) => client.is_connected(),
+      Self::Transport(transport) => transport.0.is_connected(),
+    }
   }
 
   #[cfg(feature = "serde")]
-  pub async fn subscribe<'a, Notif>(
+  async fn subscribe<'a, Notif>(
     &self,
     subscribe_method: &'a str,
     params: ArrayParams,
     unsubscribe_method: &'a str,
   ) -> Result<Subscription<Notif>>
   where
-    Notif: DeserializeOwned,
+    Notif: DeserializeOwned + Send + 'static,
   {
-    Ok(match &self.client {
+    let sub = match self {
       #[cfg(not(target_arch = "wasm32"))]
-      InnerRpcClient::Ws(ws) => {
+      Self::Ws(ws) => {
         ws.subscribe(subscribe_method, params, unsubscribe_method)
           .await
       }
       #[cfg(not(target_arch = "wasm32"))]
-      InnerRpcClient::Http(http) => {
+      Self::Http(http) => {
         http
           .subscribe(subscribe_method, params, unsubscribe_method)
           .await
       }
       #[cfg(target_arch = "wasm32")]
-      InnerRpcClient::Wasm(http) => {
+      Self::Wasm(http) => {
         http
           .subscribe(subscribe_method, params, unsubscribe_method)
           .await
       }
-    }?)
+      Self::Transport(transport) => {
+        return transport
+          .subscribe(subscribe_method, params, unsubscribe_method)
+          .await;
+      }
+    }?;
+    Ok(Subscription::new(sub))
   }
 
   #[cfg(feature = "serde")]
-  pub async fn request<'a, R>(&self, method: &'a str, params: ArrayParams) -> Result<R>
+  async fn request<R>(&self, method: &str, params: ArrayParams) -> Result<R>
   where
     R: DeserializeOwned,
   {
-    Ok(match &self.client {
+    Ok(match self {
       #[cfg(not(target_arch = "wasm32"))]
-      InnerRpcClient::Ws(ws) => ws.request(method, params).await,
+      Self::Ws(ws) => ws.request(method, params).await,
       #[cfg(not(target_arch = "wasm32"))]
-      InnerRpcClient::Http(http) => http.request(method, params).await,
+      Self::Http(http) => http.request(method, params).await,
       #[cfg(target_arch = "wasm32")]
-      InnerRpcClient::Wasm(http) => http.request(method, params).await,
+      Self::Wasm(http) => http.request(method, params).await,
+      Self::Transport(transport) => return transport.request(method, params).await,
     }?)
   }
 
   #[cfg(feature = "serde")]
-  pub async fn batch_request<'a, R>(
+  async fn batch_request<'a, R>(
     &self,
     batch: BatchRequestBuilder<'a>,
   ) -> Result<BatchResponse<'a, R>>
   where
     R: DeserializeOwned + Default + Clone + alloc::fmt::Debug + 'a,
   {
-    Ok(match &self.client {
+    Ok(match self {
       #[cfg(not(target_arch = "wasm32"))]
-      InnerRpcClient::Ws(ws) => ws.batch_request(batch).await,
+      Self::Ws(ws) => ws.batch_request(batch).await,
       #[cfg(not(target_arch = "wasm32"))]
-      InnerRpcClient::Http(http) => http.batch_request(batch).await,
+      Self::Http(http) => http.batch_request(batch).await,
       #[cfg(target_arch = "wasm32")]
-      InnerRpcClient::Wasm(http) => http.batch_request(batch).await,
+      Self::Wasm(http) => http.batch_request(batch).await,
+      Self::Transport(transport) => return transport.batch_request(batch).await,
     }?)
   }
 }
+
+#[derive(Debug)]
+struct RpcState {
+  url: String,
+  client: RwLock<Arc<InnerRpcClient>>,
+  #[cfg(not(target_arch = "wasm32"))]
+  reconnect: Option<ReconnectConfig>,
+  #[cfg(not(target_arch = "wasm32"))]
+  reconnecting: tokio::sync::Mutex<()>,
+}
+
+#[derive(Clone, Debug)]
+pub struct RpcClient {
+  state: Arc<RpcState>,
+}
+
+impl RpcClient {
+  pub async fn new(url: &str) -> Result<Self> {
+    let client = InnerRpcClient::new(url).await?;
+    Ok(Self {
+      state: Arc::new(RpcState {
+        url: url.into(),
+        client: RwLock::new(Arc::new(client)),
+        #[cfg(not(target_arch = "wasm32"))]
+        reconnect: None,
+        #[cfg(not(target_arch = "wasm32"))]
+        reconnecting: Default::default(),
+      }),
+    })
+  }
+
+  /// Create a RPC client that will reconnect to the node if the websocket connection is lost.
+  ///
+  /// Idempotent requests that fail because of the lost connection are retried after
+  /// reconnecting and subscriptions are re-issued, so their streams continue.
+  /// Notifications sent while disconnected are not recovered.
+  #[cfg(not(target_arch = "wasm32"))]
+  pub async fn new_with_reconnect(url: &str, reconnect: ReconnectConfig) -> Result<Self> {
+    let client = InnerRpcClient::new(url).await?;
+    Ok(Self {
+      state: Arc::new(RpcState {
+        url: url.into(),
+        client: RwLock::new(Arc::new(client)),
+        reconnect: Some(reconnect),
+        reconnecting: Default::default(),
+      }),
+    })
+  }
+
+  /// Create a RPC client that uses a custom transport.
+  ///
+  /// The `url` is only used to identify the backend in logs.
+  pub fn new_with_transport(url: &str, transport: impl RpcTransport) -> Self {
+    let client = InnerRpcClient::Transport(TransportClient(Arc::new(transport)));
+    Self {
+      state: Arc::new(RpcState {
+        url: url.into(),
+        client: RwLock::new(Arc::new(client)),
+        #[cfg(not(target_arch = "wasm32"))]
+        reconnect: None,
+        #[cfg(not(target_arch = "wasm32"))]
+        reconnecting: Default::default(),
+      }),
+    }
+  }
+
+  /// Create a RPC client that uses a custom transport and calls [`RpcTransport::reconnect`]
+  /// when the connection is lost.
+  ///
+  /// See [`Self::new_with_reconnect`].
+  #[cfg(not(target_arch = "wasm32"))]
+  pub fn new_with_transport_reconnect(
+    url: &str,
+    transport: impl RpcTransport,
+    reconnect: ReconnectConfig,
+  ) -> Self {
+    let client = InnerRpcClient::Transport(TransportClient(Arc::new(transport)));
+    Self {
+      state: Arc::new(RpcState {
+        url: url.into(),
+        client: RwLock::new(Arc::new(client)),
+        reconnect: Some(reconnect),
+        reconnecting: Default::default(),
+      }),
+    }
+  }
+
+  /// The url of the node.
+  pub fn url(&self) -> &str {
+    &self.state.url
+  }
+
+  /// Returns `false` if the connection to the node has been lost.
+  pub fn is_connected(&self) -> bool {
+    self.current().is_connected()
+  }
+
+  fn current(&self) -> Arc<InnerRpcClient> {
+    self
+      .state
+      .client
+      .read()
+      .expect("RpcClient lock poisoned")
+      .clone()
+  }
+
+  fn can_reconnect(&self) -> bool {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+      self.state.reconnect.is_some()
+    }
+    #[cfg(target_arch = "wasm32")]
+    false
+  }
+
+  /// Get a connected client, reconnecting first if the connection was lost.
+  async fn connected(&self) -> Result<Arc<InnerRpcClient>> {
+    let client = self.current();
+    if self.can_reconnect() && !client.is_connected() {
+      return self.reconnect(&client).await;
+    }
+    Ok(client)
+  }
+
+  /// Replace the `failed` client with a new connection.
+  #[cfg(not(target_arch = "wasm32"))]
+  async fn reconnect(&self, failed: &Arc<InnerRpcClient>) -> Result<Arc<InnerRpcClient>> {
+    let config = match &self.state.reconnect {
+      Some(config) => config,
+      None => return Err(Error::ConnectionLost("Connection to node lost".into())),
+    };
+    // Only allow one task to reconnect.
+    let _guard = self.state.reconnecting.lock().await;
+    // Check if another task already reconnected.
+    let current = self.current();
+    if !Arc::ptr_eq(&current, failed) && current.is_connected() {
+      return Ok(current);
+    }
+    let mut attempt = 0;
+    loop {
+      let delay = config.delay(attempt);
+      log::warn!(
+        "Connection to {} lost, reconnecting in {:?}",
+        self.state.url,
+        delay
+      );
+      tokio::time::sleep(delay).await;
+      match failed.reconnect(&self.state.url).await {
+        Ok(client) => {
+          log::info!("Reconnected to {}", self.state.url);
+          let client = Arc::new(client);
+          *self.state.client.write().expect("RpcClient lock poisoned") = client.clone();
+          return Ok(client);
+        }
+        Err(err) => {
+          attempt += 1;
+          if config.max_attempts.is_some_and(|max| attempt >= max) {
+            return Err(Error::ConnectionLost(format!(
+              "Failed to reconnect to {} after {attempt} attempts: {err}",
+              self.state.url
+            )));
+          }
+        }
+      }
+    }
+  }
+
+  #[cfg(target_arch = "wasm32")]
+  async fn reconnect(&self, _failed: &Arc<InnerRpcClient>) -> Result<Arc<InnerRpcClient>> {
+    Err(Error::ConnectionLost("Connection to node lost".into()))
+  }
+
+  /// Check if a failed call should be retried after reconnecting.
+  fn should_retry(&self, client: &InnerRpcClient, idempotent: bool) -> bool {
+    self.can_reconnect() && idempotent && !client.is_connected()
+  }
+
+  #[cfg(feature = "serde")]
+  pub async fn subscribe<'a, Notif>(
+    &self,
+    subscribe_method: &'a str,
+    params: ArrayParams,
+    unsubscribe_method: &'a str,
+  ) -> Result<Subscription<Notif>>
+  where
+    Notif: DeserializeOwned + Send + 'static,
+  {
+    let client = self.connected().await?;
+    let sub = client
+      .subscribe(subscribe_method, params.clone(), unsubscribe_method)
+      .await?;
+    if !self.should_resubscribe(subscribe_method) {
+      return Ok(sub);
+    }
+
+    // Re-issue the subscription if the connection is lost.
+    let rpc = self.clone();
+    let subscribe_method = subscribe_method.to_string();
+    let unsubscribe_method = unsubscribe_method.to_string();
+    let stream = async_stream::stream! {
+      let mut client = client;
+      let mut sub = sub;
+      loop {
+        match sub.next().await {
+          Some(Ok(notif)) => yield Ok(notif),
+          Some(Err(err)) if client.is_connected() => yield Err(err),
+          None if client.is_connected() => {
+            // The node closed the subscription.
+            break;
+          }
+          _ => {
+            // Connection lost.  Reconnect and subscribe again.
+            let res = match rpc.reconnect(&client).await {
+              Ok(new_client) => {
+                client = new_client;
+                client
+                  .subscribe(&subscribe_method, params.clone(), &unsubscribe_method)
+                  .await
+              }
+              Err(err) => Err(err),
+            };
+            match res {
+              Ok(new_sub) => {
+                sub = new_sub;
+              }
+              Err(err) => {
+                yield Err(err);
+                break;
+              }
+            }
+          }
+        }
+      }
+    };
+    Ok(Subscription {
+      stream: Box::pin(stream),
+    })
+  }
+
+  fn should_resubscribe(&self, method: &str) -> bool {
+    self.can_reconnect() && is_idempotent(method)
+  }
+
+  #[cfg(feature = "serde")]
+  pub async fn request<R>(&self, method: &str, params: ArrayParams) -> Result<R>
+  where
+    R: DeserializeOwned,
+  {
+    let mut client = self.connected().await?;
+    loop {
+      match client.request(method, params.clone()).await {
+        Err(err) if self.should_retry(&client, is_idempotent(method)) => {
+          log::debug!("Retry request '{method}' after connection lost: {err:?}");
+          client = self.reconnect(&client).await?;
+        }
+        res => {
+          return res;
+        }
+      }
+    }
+  }
+
+  #[cfg(feature = "serde")]
+  pub async fn batch_request<'a, R>(
+    &self,
+    batch: BatchRequestBuilder<'a>,
+  ) -> Result<BatchResponse<'a, R>>
+  where
+    R: DeserializeOwned + Default + Clone + alloc::fmt::Debug + 'a,
+  {
+    let mut client = self.connected().await?;
+    loop {
+      match client.batch_request(batch.clone()).await {
+        // Batches are used for queries, so they are treated as idempotent.
+        Err(err) if self.should_retry(&client, true) => {
+          log::debug!("Retry batch request after connection lost: {err:?}");
+          client = self.reconnect(&client).await?;
+        }
+        res => {
+          return res;
+        }
+      }
+    }
+  }
+}
+
+/// A stream of notifications from a RPC subscription.
+///
+/// When the `RpcClient` was created with reconnect support, the subscription
+/// is re-issued after reconnecting and the stream continues.
+pub struct Subscription<Notif> {
+  stream: Pin<Box<dyn Stream<Item = Result<Notif>> + Send>>,
+}
+
+#[cfg(feature = "serde")]
+impl<Notif: DeserializeOwned + Send + 'static> Subscription<Notif> {
+  fn new(sub: jsonrpsee::core::client::Subscription<Notif>) -> Self {
+    let stream = async_stream::stream! {
+      let mut sub = sub;
+      while let Some(res) = sub.next().await {
+        yield res.map_err(Error::from);
+      }
+    };
+    Self {
+      stream: Box::pin(stream),
+    }
+  }
+}
+
+impl<Notif> Subscription<Notif> {
+  /// Wait for the next notification.  Returns `None` when the subscription has ended.
+  pub async fn next(&mut self) -> Option<Result<Notif>> {
+    core::future::poll_fn(|cx| self.stream.as_mut().poll_next(cx)).await
+  }
+}
+
+impl<Notif> Stream for Subscription<Notif> {
+  type Item = Result<Notif>;
+
+  fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+    self.stream.as_mut().poll_next(cx)
+  }
+}
+
+impl<Notif> core::fmt::Debug for Subscription<Notif> {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    f.debug_struct("Subscription").finish_non_exhaustive()
+  }
+}
+
+/// Options for a client connected to multiple RPC endpoints.
+#[derive(Clone, Debug)]
+pub struct EndpointsConfig {
+  /// Spread read-only queries for a specific block across all healthy endpoints.
+  ///
+  /// Queries of the latest chain state (account nonce, best block, etc...) and
+  /// transaction submission always use the active endpoint.
+  pub round_robin: bool,
+  /// How often to check the health of the endpoints.  `None` disables the background check.
+  #[cfg(not(target_arch = "wasm32"))]
+  pub health_check_interval: Option<Duration>,
+  /// Backoff for re-issuing subscriptions when the endpoint of a subscription is lost
+  /// and no other endpoint is reachable.
+  #[cfg(not(target_arch = "wasm32"))]
+  pub resubscribe: ReconnectConfig,
+}
+
+impl Default for EndpointsConfig {
+  fn default() -> Self {
+    Self {
+      round_robin: false,
+      #[cfg(not(target_arch = "wasm32"))]
+      health_check_interval: Some(Duration::from_secs(30)),
+      #[cfg(not(target_arch = "wasm32"))]
+      resubscribe: ReconnectConfig::default(),
+    }
+  }
+}
+
+/// Get the genesis hash of the node's chain.
+async fn get_genesis(rpc: &RpcClient) -> Result<crate::BlockHash> {
+  let hash: Option<crate::BlockHash> = rpc
+    .request("chain_getBlockHash", jsonrpsee::rpc_params!(0u32))
+    .await?;
+  hash.ok_or_else(|| Error::RpcClient("Failed to get chain Genesis hash".into()))
+}
+
+/// Check if the error was caused by the node (connection lost, timeout) and
+/// not by the request itself.
+pub(crate) fn is_node_error(err: &Error) -> bool {
+  use jsonrpsee::core::Error as RpcError;
+  matches!(
+    err,
+    Error::Jsonrpsee(RpcError::Transport(_))
+      | Error::Jsonrpsee(RpcError::RestartNeeded(_))
+      | Error::Jsonrpsee(RpcError::RequestTimeout)
+      | Error::ConnectionLost(_)
+  )
+}
+
+#[derive(Debug)]
+struct Endpoint {
+  url: String,
+  rpc: RwLock<Option<RpcClient>>,
+  healthy: AtomicBool,
+  /// The endpoint's genesis hash matches the pool's genesis hash.
+  verified: AtomicBool,
+  /// The pool will reconnect to this endpoint when the connection is lost.
+  managed: bool,
+}
+
+impl Endpoint {
+  fn rpc(&self) -> Option<RpcClient> {
+    self.rpc.read().expect("Endpoint lock poisoned").clone()
+  }
+
+  fn set_rpc(&self, rpc: Option<RpcClient>) {
+    *self.rpc.write().expect("Endpoint lock poisoned") = rpc;
+  }
+
+  fn is_healthy(&self) -> bool {
+    self.healthy.load(Ordering::Relaxed)
+  }
+
+  fn set_healthy(&self, healthy: bool) {
+    self.healthy.store(healthy, Ordering::Relaxed);
+  }
+
+  fn is_verified(&self) -> bool {
+    self.verified.load(Ordering::Relaxed)
+  }
+
+  /// Connect to the endpoint, if not already connected.
+  async fn connect(&self) -> Result<RpcClient> {
+    match self.rpc() {
+      Some(rpc) if !self.managed || rpc.is_connected() => Ok(rpc),
+      _ => {
+        let rpc = RpcClient::new(&self.url).await?;
+        self.set_rpc(Some(rpc.clone()));
+        Ok(rpc)
+      }
+    }
+  }
+}
+
+#[derive(Debug)]
+struct PoolState {
+  endpoints: Vec<Endpoint>,
+  /// The genesis hash of the chain.  Endpoints must match it before they are used.
+  genesis: Option<crate::BlockHash>,
+  active: AtomicUsize,
+  next: AtomicUsize,
+  round_robin: bool,
+  #[cfg(not(target_arch = "wasm32"))]
+  resubscribe: ReconnectConfig,
+}
+
+/// A pool of RPC endpoints for the same chain.
+///
+/// Requests are sent to the active endpoint and fail over to the next healthy
+/// endpoint if the node doesn't respond.  Subscriptions are re-issued on the next
+/// healthy endpoint when the connection to their endpoint is lost.
+#[derive(Clone, Debug)]
+pub struct RpcPool {
+  state: Arc<PoolState>,
+}
+
+impl RpcPool {
+  /// Pool with a single endpoint.
+  pub fn single(rpc: RpcClient) -> Self {
+    let endpoint = Endpoint {
+      url: rpc.url().into(),
+      rpc: RwLock::new(Some(rpc)),
+      healthy: AtomicBool::new(true),
+      verified: AtomicBool::new(true),
+      managed: false,
+    };
+    Self {
+      state: Arc::new(PoolState {
+        endpoints: vec![endpoint],
+        genesis: None,
+        active: AtomicUsize::new(0),
+        next: AtomicUsize::new(0),
+        round_robin: false,
+        #[cfg(not(target_arch = "wasm32"))]
+        resubscribe: ReconnectConfig::default(),
+      }),
+    }
+  }
+
+  /// Connect to multiple endpoints.  At least one endpoint must be reachable and
+  /// all reachable endpoints must have the same genesis hash.
+  ///
+  /// Unreachable endpoints are marked unhealthy.  Their genesis hash is checked
+  /// before they are used.
+  pub async fn connect(urls: &[&str], config: EndpointsConfig) -> Result<Self> {
+    let endpoints = urls
+      .iter()
+      .map(|url| Endpoint {
+        url: url.to_string(),
+        rpc: RwLock::new(None),
+        healthy: AtomicBool::new(false),
+        verified: AtomicBool::new(false),
+        managed: true,
+      })
+      .collect();
+    Self::new(endpoints, config).await
+  }
+
+  /// Create a pool from RPC clients that are already connected (e.g. with a custom transport).
+  ///
+  /// The pool doesn't reconnect these clients, use [`RpcClient::new_with_reconnect`] for that.
+  pub async fn from_clients(clients: Vec<RpcClient>, config: EndpointsConfig) -> Result<Self> {
+    let endpoints = clients
+      .into_iter()
+      .map(|rpc| Endpoint {
+        url: rpc.url().into(),
+        rpc: RwLock::new(Some(rpc)),
+        healthy: AtomicBool::new(false),
+        verified: AtomicBool::new(false),
+        managed: false,
+      })
+      .collect();
+    Self::new(endpoints, config).await
+  }
+
+  async fn new(endpoints: Vec<Endpoint>, config: EndpointsConfig) -> Result<Self> {
+    if endpoints.is_empty() {
+      return Err(Error::RpcClient("No RPC endpoints".into()));
+    }
+    let mut genesis = None;
+    for endpoint in &endpoints {
+      let url = &endpoint.url;
+      let res = match endpoint.connect().await {
+        Ok(rpc) => get_genesis(&rpc).await,
+        Err(err) => Err(err),
+      };
+      match res {
+        Ok(hash) => {
+          match genesis {
+            None => genesis = Some(hash),
+            Some(genesis) if genesis != hash => {
+              return Err(Error::RpcClient(format!(
+                "Endpoint {url} is for a different chain: genesis {hash:?} != {genesis:?}"
+              )));
+            }
+            _ => (),
+          }
+          endpoint.verified.store(true, Ordering::Relaxed);
+          endpoint.set_healthy(true);
+        }
+        Err(err) => {
+          log::warn!("Failed to connect to RPC endpoint {url}: {err:?}");
+        }
+      }
+    }
+    let active = endpoints
+      .iter()
+      .position(|e| e.is_healthy())
+      .ok_or_else(|| Error::RpcClient("Failed to connect to any RPC endpoint".into()))?;
+    let pool = Self {
+      state: Arc::new(PoolState {
+        endpoints,
+        genesis,
+        active: AtomicUsize::new(active),
+        next: AtomicUsize::new(active),
+        round_robin: config.round_robin,
+        #[cfg(not(target_arch = "wasm32"))]
+        resubscribe: config.resubscribe,
+      }),
+    };
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some(interval) = config.health_check_interval {
+      pool.spawn_health_check(interval);
+    }
+    Ok(pool)
+  }
+
+  #[cfg(not(target_arch = "wasm32"))]
+  fn spawn_health_check(&self, interval: Duration) {
+    let handle = match tokio::runtime::Handle::try_current() {
+      Ok(handle) => handle,
+      Err(_) => {
+        log::warn!("Not running inside a tokio runtime, the RPC endpoint health check is disabled");
+        return;
+      }
+    };
+    let state = Arc::downgrade(&self.state);
+    handle.spawn(async move {
+      loop {
+        tokio::time::sleep(interval).await;
+        // Stop when the pool is dropped.
+        match state.upgrade() {
+          Some(state) => RpcPool { state }.check_health().await,
+          None => break,
+        }
+      }
+    });
+  }
+
+  /// The urls of all endpoints.
+  pub fn urls(&self) -> Vec<&str> {
+    self
+      .state
+      .endpoints
+      .iter()
+      .map(|e| e.url.as_str())
+      .collect()
+  }
+
+  /// The url of the active endpoint.
+  pub fn active_url(&self) -> &str {
+    &self.state.endpoints[self.active()].url
+  }
+
+  fn active(&self) -> usize {
+    self.state.active.load(Ordering::Relaxed)
+  }
+
+  /// Check that the endpoint is for the same chain as the pool, the first time it is used.
+  async fn verify_endpoint(&self, endpoint: &Endpoint, rpc: &RpcClient) -> Result<()> {
+    if endpoint.is_verified() {
+      return Ok(());
+    }
+    if let Some(genesis) = self.state.genesis {
+      let hash = get_genesis(rpc).await?;
+      if hash != genesis {
+        return Err(Error::RpcClient(format!(
+          "Endpoint {} is for a different chain: genesis {hash:?} != {genesis:?}",
+          endpoint.url
+        )));
+      }
+    }
+    endpoint.verified.store(true, Ordering::Relaxed);
+    Ok(())
+  }
+
+  /// Check the health of all endpoints using `system_health`.
+  ///
+  /// Disconnected endpoints are reconnected.  A node is healthy if it responds,
+  /// isn't syncing and has the same genesis hash as the pool.
+  pub async fn check_health(&self) {
+    for (idx, endpoint) in self.state.endpoints.iter().enumerate() {
+      let res = match endpoint.connect().await {
+        Ok(rpc) => match self.verify_endpoint(endpoint, &rpc).await {
+          Ok(()) => {
+            rpc
+              .request::<crate::SystemHealth>("system_health", jsonrpsee::rpc_params!())
+              .await
+          }
+          Err(err) => Err(err),
+        },
+        Err(err) => Err(err),
+      };
+      let healthy = match res {
+        Ok(health) => !health.is_syncing,
+        Err(err) => {
+          log::debug!("Health check failed for {}: {err:?}", endpoint.url);
+          false
+        }
+      };
+      if endpoint.is_healthy() != healthy {
+        log::info!("RPC endpoint {} healthy: {healthy}", endpoint.url);
+      }
+      endpoint.set_healthy(healthy);
+      if !healthy && idx == self.active() {
+        self.failover(idx);
+      }
+    }
+  }
+
+  /// Move the active endpoint away from `failed`.
+  fn failover(&self, failed: usize) {
+    let state = &self.state;
+    state.endpoints[failed].set_healthy(false);
+    let len = state.endpoints.len();
+    if let Some(next) = (1..len)
+      .map(|off| (failed + off) % len)
+      .find(|idx| state.endpoints[*idx].is_healthy())
+    {
+      if state
+        .active
+        .compare_exchange(failed, next, Ordering::Relaxed, Ordering::Relaxed)
+        .is_ok()
+      {
+        log::warn!(
+          "Failover from RPC endpoint {} to {}",
+          state.endpoints[failed].url,
+          state.endpoints[next].url
+        );
+      }
+    }
+  }
+
+  /// The order to try the endpoints in, healthy endpoints first.
+  fn candidates(&self, read_only: bool) -> Vec<usize> {
+    let state = &self.state;
+    let len = state.endpoints.len();
+    let start = if read_only && state.round_robin {
+      state.next.fetch_add(1, Ordering::Relaxed) % len
+    } else {
+      self.active()
+    };
+    let (mut healthy, unhealthy): (Vec<_>, Vec<_>) = (0..len)
+      .map(|off| (start + off) % len)
+      .partition(|idx| state.endpoints[*idx].is_healthy());
+    healthy.extend(unhealthy);
+    healthy
+  }
+
+  /// Call `f` with the endpoints in order, until one of them doesn't fail with a node error.
+  async fn with_failover<T, F, Fut>(&self, read_only: bool, idempotent: bool, f: F) -> Result<T>
+  where
+    F: Fn(RpcClient) -> Fut,
+    Fut: core::future::Future<Output = Result<T>>,
+  {
+    let (_, res) = self.try_endpoints(read_only, idempotent, f).await?;
+    Ok(res)
+  }
+
+  /// Same as [`Self::with_failover`], but also returns the index of the endpoint used.
+  async fn try_endpoints<T, F, Fut>(
+    &self,
+    read_only: bool,
+    idempotent: bool,
+    f: F,
+  ) -> Result<(usize, T)>
+  where
+    F: Fn(RpcClient) -> Fut,
+    Fut: core::future::Future<Output = Result<T>>,
+  {
+    let mut last_err = None;
+    for idx in self.candidates(read_only) {
+      let endpoint = &self.state.endpoints[idx];
+      let rpc = match endpoint.connect().await {
+        Ok(rpc) => rpc,
+        Err(err) => {
+          log::warn!("RPC endpoint {} failed: {err:?}", endpoint.url);
+          self.failover(idx);
+          last_err = Some(err);
+          continue;
+        }
+      };
+      if let Err(err) = self.verify_endpoint(endpoint, &rpc).await {
+        log::warn!("Skip RPC endpoint {}: {err:?}", endpoint.url);
+        self.failover(idx);
+        last_err = Some(err);
+        continue;
+      }
+      match f(rpc).await {
+        Err(err) if is_node_error(&err) => {
+          log::warn!("RPC endpoint {} failed: {err:?}", endpoint.url);
+          self.failover(idx);
+          if !idempotent {
+            return Err(err);
+          }
+          last_err = Some(err);
+        }
+        res => {
+          if !endpoint.is_healthy() {
+            endpoint.set_healthy(true);
+          }
+          return res.map(|res| (idx, res));
+        }
+      }
+    }
+    Err(last_err.unwrap_or_else(|| Error::RpcClient("No RPC endpoints".into())))
+  }
+
+  /// Subscribe on the first healthy endpoint.
+  #[cfg(feature = "serde")]
+  async fn subscribe_endpoint<Notif>(
+    &self,
+    subscribe_method: &str,
+    params: &ArrayParams,
+    unsubscribe_method: &str,
+  ) -> Result<(usize, (RpcClient, Subscription<Notif>))>
+  where
+    Notif: DeserializeOwned + Send + 'static,
+  {
+    self
+      .try_endpoints(false, is_idempotent(subscribe_method), |rpc| {
+        let params = params.clone();
+        async move {
+          let sub = rpc
+            .subscribe(subscribe_method, params, unsubscribe_method)
+            .await?;
+          Ok((rpc, sub))
+        }
+      })
+      .await
+  }
+
+  #[cfg(feature = "serde")]
+  pub async fn subscribe<'a, Notif>(
+    &self,
+    subscribe_method: &'a str,
+    params: ArrayParams,
+    unsubscribe_method: &'a str,
+  ) -> Result<Subscription<Notif>>
+  where
+    Notif: DeserializeOwned + Send + 'static,
+  {
+    let (idx, (rpc, sub)) = self
+      .subscribe_endpoint(subscribe_method, &params, unsubscribe_method)
+      .await?;
+    #[cfg(target_arch = "wasm32")]
+    return Ok(sub);
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+      if self.state.endpoints.len() == 1 || !is_idempotent(subscribe_method) {
+        return Ok(sub);
+      }
+
+      // Re-issue the subscription on the next healthy endpoint if the endpoint is lost.
+      let pool = self.clone();
+      let subscribe_method = subscribe_method.to_string();
+      let unsubscribe_method = unsubscribe_method.to_string();
+      let stream = async_stream::stream! {
+        let (mut idx, mut rpc, mut sub) = (idx, rpc, sub);
+        'subscription: loop {
+          match sub.next().await {
+            Some(Ok(notif)) => yield Ok(notif),
+            Some(Err(err)) if rpc.is_connected() => yield Err(err),
+            None if rpc.is_connected() => {
+              // The node closed the subscription.
+              break;
+            }
+            _ => {
+              log::warn!("Subscription '{subscribe_method}' lost on RPC endpoint {}", rpc.url());
+              pool.failover(idx);
+              let config = &pool.state.resubscribe;
+              let mut attempt = 0;
+              loop {
+                match pool
+                  .subscribe_endpoint(&subscribe_method, &params, &unsubscribe_method)
+                  .await
+                {
+                  Ok((new_idx, (new_rpc, new_sub))) => {
+                    (idx, rpc, sub) = (new_idx, new_rpc, new_sub);
+                    break;
+                  }
+                  Err(err) => {
+                    if config.max_attempts.is_some_and(|max| attempt + 1 >= max) {
+                      yield Err(err);
+                      break 'subscription;
+                    }
+                    tokio::time::sleep(config.delay(attempt)).await;
+                    attempt += 1;
+                  }
+                }
+              }
+            }
+          }
+        }
+      };
+      Ok(Subscription {
+        stream: Box::pin(stream),
+      })
+    }
+  }
+
+  /// Send a request to the active endpoint.
+  #[cfg(feature = "serde")]
+  pub async fn request<R>(&self, method: &str, params: ArrayParams) -> Result<R>
+  where
+    R: DeserializeOwned,
+  {
+    self
+      .with_failover(false, is_idempotent(method), |rpc| {
+        let params = params.clone();
+        async move { rpc.request(method, params).await }
+      })
+      .await
+  }
+
+  /// Send a read-only request.  If round-robin is enabled the request can be sent
+  /// to any healthy endpoint.
+  ///
+  /// Only use this for requests that will return the same results from any node
+  /// (i.e. queries at a specific block).  An endpoint that is behind might not have
+  /// the block yet, so `null` results and errors from an endpoint other than the
+  /// active endpoint are checked with the active endpoint.
+  #[cfg(feature = "serde")]
+  pub async fn read_request<R>(&self, method: &str, params: ArrayParams) -> Result<R>
+  where
+    R: DeserializeOwned,
+  {
+    let (idx, res) = self
+      .try_endpoints(true, true, |rpc| {
+        let params = params.clone();
+        async move {
+          match rpc.request::<Value>(method, params).await {
+            // Keep errors from the request itself, to retry them with the active endpoint.
+            Err(err) if !is_node_error(&err) => Ok(Err(err)),
+            res => res.map(Ok),
+          }
+        }
+      })
+      .await?;
+    let res = match res {
+      Ok(Value::Null) | Err(_) if idx != self.active() => {
+        self.request::<Value>(method, params).await
+      }
+      res => res,
+    };
+    Ok(serde_json::from_value(res?)?)
+  }
+
+  #[cfg(feature = "serde")]
+  pub async fn batch_request<'a, R>(
+    &self,
+    batch: BatchRequestBuilder<'a>,
+  ) -> Result<BatchResponse<'a, R>>
+  where
+    R: DeserializeOwned + Default + Clone + alloc::fmt::Debug + 'a,
+  {
+    self
+      .with_failover(false, true, |rpc| {
+        let batch = batch.clone();
+        async move { rpc.batch_request(batch).await }
+      })
+      .await
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_idempotent_methods() {
+    assert!(is_idempotent("state_getStorage"));
+    assert!(is_idempotent("chain_subscribeNewHeads"));
+    assert!(!is_idempotent("author_submitExtrinsic"));
+    assert!(!is_idempotent("author_submitAndWatchExtrinsic"));
+  }
+
+  #[cfg(not(target_arch = "wasm32"))]
+  #[test]
+  fn test_reconnect_backoff() {
+    let config = ReconnectConfig {
+      initial_delay: Duration::from_millis(100),
+      max_delay: Duration::from_secs(1),
+      max_attempts: None,
+    };
+    let delays = (0..5).map(|attempt| config.delay(attempt).as_millis());
+    assert_eq!(delays.collect::<Vec<_>>(), vec![100, 200, 400, 800, 1000]);
+    assert_eq!(config.delay(100), config.max_delay);
+  }
+}
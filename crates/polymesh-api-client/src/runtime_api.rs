@@ -0,0 +1,85 @@
+use core::marker::PhantomData;
+
+use codec::Decode;
+
+use sp_std::prelude::*;
+
+use crate::*;
+
+/// A runtime API call with SCALE encoded arguments.
+///
+/// The generated runtime API wrappers build these from typed arguments.
+/// `T` is the type returned by the runtime API method.
+pub struct RuntimeApiCall<T> {
+  method: &'static str,
+  args: Vec<u8>,
+  _output: PhantomData<fn() -> T>,
+}
+
+impl<T> Clone for RuntimeApiCall<T> {
+  fn clone(&self) -> Self {
+    Self {
+      method: self.method,
+      args: self.args.clone(),
+      _output: PhantomData,
+    }
+  }
+}
+
+impl<T> core::fmt::Debug for RuntimeApiCall<T> {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    f.debug_struct("RuntimeApiCall")
+      .field("method", &self.method)
+      .field("args", &hex::encode(&self.args))
+      .finish()
+  }
+}
+
+impl<T: Decode> RuntimeApiCall<T> {
+  pub fn new(method: &'static str, args: Vec<u8>) -> Self {
+    Self {
+      method,
+      args,
+      _output: PhantomData,
+    }
+  }
+
+  /// The runtime method name (e.g. `TransactionPaymentApi_query_info`).
+  pub fn method(&self) -> &'static str {
+    self.method
+  }
+
+  /// The SCALE encoded arguments.
+  pub fn args(&self) -> &[u8] {
+    &self.args
+  }
+
+  /// Decode the result of the runtime API call.
+  pub fn decode_output(&self, mut data: &[u8]) -> Result<T> {
+    Ok(T::decode(&mut data)?)
+  }
+
+  /// Call the runtime API at block `at` (the best block if `None`) and decode the result.
+  pub async fn call(&self, client: &Client, at: Option<BlockHash>) -> Result<T> {
+    let data = client.state_call(self.method, &self.args, at).await?;
+    self.decode_output(&data)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use codec::Encode;
+
+  #[test]
+  fn test_runtime_api_call() -> anyhow::Result<()> {
+    let account = AccountId::default();
+    let call = RuntimeApiCall::<u32>::new("AccountNonceApi_account_nonce", account.encode());
+    assert_eq!(call.method(), "AccountNonceApi_account_nonce");
+    assert_eq!(call.args(), account.encode().as_slice());
+    assert_eq!(call.decode_output(&5u32.encode())?, 5);
+    assert!(call.decode_output(&[1]).is_err());
+    Ok(())
+  }
+}
@@ -756,6 +756,17 @@ impl InnerTypesRegistry {
 
         Metadata::from_v14_metadata(v14, &mut types)?
       }
+      #[cfg(feature = "v15")]
+      RuntimeMetadata::V15(v15) => {
+        if runtime_metadata.0 != frame_metadata::META_RESERVED {
+          return Err(Error::MetadataParseFailed(format!(
+            "Invalid metadata prefix {}",
+            runtime_metadata.0
+          )));
+        }
+
+        Metadata::from_v15_metadata(v15, &mut types)?
+      }
       _ => {
         return Err(Error::MetadataParseFailed(format!(
           "Unsupported metadata version"
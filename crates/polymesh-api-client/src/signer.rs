@@ -7,7 +7,9 @@ use sp_std::prelude::*;
 use async_trait::async_trait;
 
 #[cfg(not(feature = "std"))]
-use alloc::string::ToString;
+use alloc::{string::ToString, sync::Arc};
+#[cfg(feature = "std")]
+use std::sync::Arc;
 
 use crate::*;
 
@@ -76,6 +78,12 @@ pub mod dev {
   }
 }
 
+/// Returns unused nonces, so they can be reused by new transactions.
+pub trait NonceRollback: Send + Sync {
+  /// The transaction signed with `nonce` was rejected, dropped or never submitted.
+  fn rollback(&self, account: AccountId, nonce: u32);
+}
+
 #[async_trait]
 pub trait Signer: Send + Sync {
   fn account(&self) -> AccountId;
@@ -86,11 +94,23 @@ pub trait Signer: Send + Sync {
     None
   }
 
+  /// Optional - Reserve the `nonce` to sign a transaction with.  Only called when signing.
+  /// The default implementation returns `nonce()`.
+  async fn reserve_nonce(&self) -> Option<u32> {
+    self.nonce().await
+  }
+
   /// Optional - The signer can manage their `nonce` for improve transaction performance.
   /// If the transaction is accepted by the RPC node, then the `nonce` we be increased, to
   /// allow the next transaction to be signed & submitted without waiting for the next block.
   async fn set_nonce(&mut self, _nonce: u32) {}
 
+  /// Optional - Used by signers that manage their `nonce` to get back the `nonce`
+  /// of transactions that are invalid or dropped from the transaction pool.
+  fn nonce_rollback(&self) -> Option<Arc<dyn NonceRollback>> {
+    None
+  }
+
   async fn sign(&self, msg: &[u8]) -> Result<MultiSignature>;
 
   /// Optional support for locking the signer.
@@ -104,11 +124,27 @@ pub trait KeypairSigner: Send + Sync + Sized + Clone {
   fn sign(&self, message: &[u8]) -> MultiSignature;
   fn from_string(s: &str, password_override: Option<&str>) -> Result<Self>;
 
+  #[cfg(feature = "std")]
+  fn verify<M: AsRef<[u8]>>(&self, sig: &MultiSignature, message: M) -> Result<bool> {
+    Ok(verify_signature(&self.account(), sig, message.as_ref()))
+  }
+
+  #[cfg(not(feature = "std"))]
   fn verify<M: AsRef<[u8]>>(&self, _sig: &MultiSignature, _message: M) -> Result<bool> {
     unimplemented!()
   }
 }
 
+/// Verify that `sig` is a valid signature of `message` from `account`.
+///
+/// Supports all signature types.  For ECDSA signatures the `account` is the
+/// `blake2_256` hash of the public key.
+#[cfg(feature = "std")]
+pub fn verify_signature(account: &AccountId, sig: &MultiSignature, message: &[u8]) -> bool {
+  use sp_runtime::traits::Verify;
+  sig.verify(message, &account.into())
+}
+
 #[cfg(feature = "std")]
 impl KeypairSigner for sp_core::ed25519::Pair {
   fn account(&self) -> AccountId {
@@ -255,10 +291,18 @@ impl Signer for Box<dyn Signer> {
     self.as_ref().nonce().await
   }
 
+  async fn reserve_nonce(&self) -> Option<u32> {
+    self.as_ref().reserve_nonce().await
+  }
+
   async fn set_nonce(&mut self, nonce: u32) {
     self.as_mut().set_nonce(nonce).await
   }
 
+  fn nonce_rollback(&self) -> Option<Arc<dyn NonceRollback>> {
+    self.as_ref().nonce_rollback()
+  }
+
   async fn sign(&self, msg: &[u8]) -> Result<MultiSignature> {
     self.as_ref().sign(msg).await
   }
@@ -0,0 +1,227 @@
+#[cfg(not(feature = "std"))]
+use alloc::{collections::btree_map::BTreeMap, format};
+#[cfg(feature = "std")]
+use std::collections::BTreeMap;
+
+use codec::{Decode, Encode};
+use sp_std::prelude::*;
+
+use crate::*;
+
+/// Magic bytes at the start of a snapshot file.
+const SNAPSHOT_MAGIC: [u8; 4] = *b"pss\0";
+/// Version of the snapshot file format.
+const SNAPSHOT_VERSION: u8 = 1;
+
+/// The storage prefix of a pallet (`twox_128(pallet_name)`).
+pub fn pallet_storage_prefix(pallet: &str) -> StorageKey {
+  StorageKey(sp_core::hashing::twox_128(pallet.as_bytes()).to_vec())
+}
+
+/// A snapshot of the raw chain storage at a block.
+///
+/// The snapshot can be saved to a compact SCALE encoded file and loaded later
+/// to answer storage queries without a node.
+#[derive(Clone, Debug, Default, Decode, Encode)]
+pub struct StorageSnapshot {
+  /// The block the storage was read from.
+  pub block: BlockHash,
+  /// The common prefix of the storage keys.  Empty for a snapshot of the whole chain.
+  pub prefix: Vec<u8>,
+  entries: BTreeMap<Vec<u8>, Vec<u8>>,
+}
+
+impl StorageSnapshot {
+  /// An empty snapshot of block `block`.
+  pub fn new(block: BlockHash, prefix: StorageKey) -> Self {
+    Self {
+      block,
+      prefix: prefix.0,
+      entries: BTreeMap::new(),
+    }
+  }
+
+  /// Download all storage entries with `prefix` at block `at`.
+  ///
+  /// Use [`pallet_storage_prefix`] to get the prefix of a pallet, or an empty
+  /// prefix for the whole chain.
+  pub async fn download(client: &Client, prefix: StorageKey, at: BlockHash) -> Result<Self> {
+    let keys = client.get_all_storage_keys(&prefix, Some(at)).await?;
+    let values = client.get_storage_data_by_keys(&keys, Some(at)).await?;
+    let entries = keys
+      .into_iter()
+      .zip(values)
+      .filter_map(|(key, value)| value.map(|value| (key.0, value.0)))
+      .collect();
+    Ok(Self {
+      block: at,
+      prefix: prefix.0,
+      entries,
+    })
+  }
+
+  /// Add the entries of another snapshot of the same block (e.g. another pallet).
+  pub fn merge(&mut self, other: StorageSnapshot) -> Result<()> {
+    if self.block != other.block {
+      return Err(Error::StorageSnapshot(format!(
+        "Can't merge snapshots of different blocks: {:?} != {:?}",
+        self.block, other.block
+      )));
+    }
+    // The common prefix of both snapshots.
+    let len = self
+      .prefix
+      .iter()
+      .zip(&other.prefix)
+      .take_while(|(a, b)| a == b)
+      .count();
+    self.prefix.truncate(len);
+    self.entries.extend(other.entries);
+    Ok(())
+  }
+
+  /// Number of storage entries.
+  pub fn len(&self) -> usize {
+    self.entries.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.entries.is_empty()
+  }
+
+  /// Insert or replace a raw storage value.
+  pub fn insert(&mut self, key: StorageKey, value: StorageData) {
+    self.entries.insert(key.0, value.0);
+  }
+
+  /// Iterate over the raw storage entries.
+  pub fn entries(&self) -> impl Iterator<Item = (StorageKey, StorageData)> + '_ {
+    self
+      .entries
+      .iter()
+      .map(|(key, value)| (StorageKey(key.clone()), StorageData(value.clone())))
+  }
+
+  pub fn get_storage_data_by_key(&self, key: &StorageKey) -> Option<StorageData> {
+    self.entries.get(&key.0).cloned().map(StorageData)
+  }
+
+  pub fn get_storage_by_key<T: Decode>(&self, key: &StorageKey) -> Result<Option<T>> {
+    let value = self
+      .entries
+      .get(&key.0)
+      .map(|data| T::decode(&mut data.as_slice()))
+      .transpose()?;
+    Ok(value)
+  }
+
+  /// Get up to `count` keys with `prefix` after `start_key`, like `state_getKeysPaged`.
+  pub fn get_storage_keys_paged(
+    &self,
+    prefix: &StorageKey,
+    count: usize,
+    start_key: Option<&StorageKey>,
+  ) -> Vec<StorageKey> {
+    use core::ops::Bound;
+    let start = match start_key {
+      Some(key) => Bound::Excluded(key.0.clone()),
+      None => Bound::Included(prefix.0.clone()),
+    };
+    self
+      .entries
+      .range((start, Bound::Unbounded))
+      .map(|(key, _)| key)
+      .take_while(|key| key.starts_with(&prefix.0))
+      .take(count)
+      .map(|key| StorageKey(key.clone()))
+      .collect()
+  }
+
+  /// Encode the snapshot for saving to a file.
+  pub fn to_bytes(&self) -> Vec<u8> {
+    let mut buf = SNAPSHOT_MAGIC.to_vec();
+    buf.push(SNAPSHOT_VERSION);
+    self.encode_to(&mut buf);
+    buf
+  }
+
+  /// Decode a snapshot from the bytes of a snapshot file.
+  pub fn from_bytes(data: &[u8]) -> Result<Self> {
+    let data = data
+      .strip_prefix(&SNAPSHOT_MAGIC[..])
+      .ok_or_else(|| Error::StorageSnapshot("Not a storage snapshot".into()))?;
+    match data.split_first() {
+      Some((&SNAPSHOT_VERSION, mut data)) => Ok(Self::decode(&mut data)?),
+      Some((version, _)) => Err(Error::StorageSnapshot(format!(
+        "Unsupported snapshot version: {version}"
+      ))),
+      None => Err(Error::StorageSnapshot("Snapshot is empty".into())),
+    }
+  }
+
+  /// Save the snapshot to a file.
+  #[cfg(feature = "std")]
+  pub fn save(&self, path: impl AsRef<std::path::Path>) -> Result<()> {
+    std::fs::write(path, self.to_bytes())?;
+    Ok(())
+  }
+
+  /// Load a snapshot from a file.
+  #[cfg(feature = "std")]
+  pub fn load(path: impl AsRef<std::path::Path>) -> Result<Self> {
+    Self::from_bytes(&std::fs::read(path)?)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_storage_snapshot() -> anyhow::Result<()> {
+    let prefix = pallet_storage_prefix("System");
+    let key = |n: u8| {
+      let mut key = prefix.0.clone();
+      key.push(n);
+      StorageKey(key)
+    };
+    let mut snapshot = StorageSnapshot {
+      block: BlockHash::repeat_byte(1),
+      prefix: prefix.0.clone(),
+      ..Default::default()
+    };
+    for n in 0..5u8 {
+      snapshot.insert(key(n), StorageData((n as u32).encode()));
+    }
+
+    let loaded = StorageSnapshot::from_bytes(&snapshot.to_bytes())?;
+    assert_eq!(loaded.block, snapshot.block);
+    assert_eq!(loaded.len(), 5);
+    assert_eq!(loaded.get_storage_by_key::<u32>(&key(3))?, Some(3));
+    assert_eq!(loaded.get_storage_by_key::<u32>(&key(9))?, None);
+
+    let page = loaded.get_storage_keys_paged(&prefix, 2, None);
+    assert_eq!(page.len(), 2);
+    let page = loaded.get_storage_keys_paged(&prefix, 10, page.last());
+    assert_eq!(page.len(), 3);
+    assert!(loaded
+      .get_storage_keys_paged(&pallet_storage_prefix("Balances"), 10, None)
+      .is_empty());
+
+    let mut bad = snapshot.to_bytes();
+    bad[4] = 99;
+    assert!(StorageSnapshot::from_bytes(&bad).is_err());
+    assert!(StorageSnapshot::from_bytes(b"meta").is_err());
+
+    let mut other = StorageSnapshot {
+      block: snapshot.block,
+      prefix: pallet_storage_prefix("Balances").0,
+      ..Default::default()
+    };
+    other.insert(StorageKey(other.prefix.clone()), StorageData(vec![1]));
+    snapshot.merge(other)?;
+    assert_eq!(snapshot.len(), 6);
+    assert!(snapshot.prefix.len() < 16);
+    Ok(())
+  }
+}
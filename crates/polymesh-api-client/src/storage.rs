@@ -1,15 +1,82 @@
 use core::marker::PhantomData;
+use core::pin::Pin;
+use core::task::{Context, Poll};
 
 use async_stream::try_stream;
 use futures_core::stream::Stream;
 
-#[cfg(not(feature = "std"))]
-use alloc::format;
 use codec::Decode;
 use sp_std::prelude::*;
 
+use crate::rpc::Subscription;
 use crate::*;
 
+/// Decode the last key of a map storage key.
+///
+/// `key_hash_len` is the length of the hash before the key, it is `None` if the hasher isn't reversible.
+pub fn decode_storage_key<K: Decode>(
+  prefix: &StorageKey,
+  key_hash_len: Option<usize>,
+  key: &StorageKey,
+) -> Result<K> {
+  let h_len = match key_hash_len {
+    Some(l) => l,
+    None => {
+      return Err(Error::DecodeTypeFailed(
+        "Failed to decode storage key: hasher isn't reversible".into(),
+      ));
+    }
+  };
+  let p_len = prefix.0.len();
+  if key.0.len() < (p_len + h_len) {
+    return Err(Error::DecodeTypeFailed(
+      "Failed to decode storage key: too short".into(),
+    ));
+  }
+  let (key_prefix, key) = key.0.split_at(p_len);
+  if key_prefix != prefix.0.as_slice() {
+    return Err(Error::DecodeTypeFailed(
+      "Invalid storage key, the prefix doesn't match".into(),
+    ));
+  }
+  Ok(K::decode(&mut &key[h_len..])?)
+}
+
+/// A changed storage entry.
+#[derive(Clone, Debug)]
+pub struct ChangedEntry<K, V> {
+  pub key: K,
+  pub old: V,
+  pub new: V,
+}
+
+/// The differences of a storage map between two blocks.
+#[derive(Clone, Debug)]
+pub struct StorageDiff<K, V> {
+  /// Entries only in the newer block.
+  pub added: Vec<(K, V)>,
+  /// Entries only in the older block.
+  pub removed: Vec<(K, V)>,
+  /// Entries with different values.
+  pub changed: Vec<ChangedEntry<K, V>>,
+}
+
+impl<K, V> Default for StorageDiff<K, V> {
+  fn default() -> Self {
+    Self {
+      added: Vec::new(),
+      removed: Vec::new(),
+      changed: Vec::new(),
+    }
+  }
+}
+
+impl<K, V> StorageDiff<K, V> {
+  pub fn is_empty(&self) -> bool {
+    self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+  }
+}
+
 /// Query chain map/double map storage with a common prefix.
 ///
 /// The `state_getKeysPaged` API is used to get batches of keys.
@@ -49,28 +116,16 @@ impl<K: Decode, V: Decode> StoragePaged<K, V> {
     self
   }
 
-  fn get_hashed_key<'a>(&self, key: &'a StorageKey) -> Result<&'a [u8]> {
-    let h_len = match self.key_hash_len {
-      Some(l) => l,
-      None => {
-        return Err(Error::DecodeTypeFailed(format!(
-          "Failed to decode storage key: hasher isn't reversible"
-        )));
-      }
-    };
-    let p_len = self.prefix.0.len();
-    if key.0.len() < (p_len + h_len) {
-      return Err(Error::DecodeTypeFailed(format!(
-        "Failed to decode storage key: too short"
-      )));
-    }
-    let (key_prefix, key) = key.0.split_at(p_len);
-    if key_prefix != self.prefix.0.as_slice() {
-      return Err(Error::DecodeTypeFailed(format!(
-        "Invalid storage key, the prefix doesn't match"
-      )));
-    }
-    Ok(&key[h_len..])
+  /// Compare the entries at `from_block` and `to_block`.
+  pub async fn diff(
+    &self,
+    from_block: BlockHash,
+    to_block: BlockHash,
+  ) -> Result<StorageDiff<K, V>> {
+    self
+      .client
+      .storage_diff(&self.prefix, self.key_hash_len, from_block, to_block)
+      .await
   }
 
   async fn next_page(&mut self) -> Result<Option<Vec<StorageKey>>> {
@@ -100,8 +155,7 @@ impl<K: Decode, V: Decode> StoragePaged<K, V> {
       while let Some(keys) = self.next_page().await? {
         for storage_key in keys {
           // Decode key.
-          let mut data = self.get_hashed_key(&storage_key)?;
-          let key = K::decode(&mut data)?;
+          let key = decode_storage_key(&self.prefix, self.key_hash_len, &storage_key)?;
           // Get value from chain storage.
           let value = self.client.get_storage_by_key(storage_key, self.at).await?;
           yield (key, value);
@@ -115,8 +169,7 @@ impl<K: Decode, V: Decode> StoragePaged<K, V> {
     try_stream! {
       while let Some(keys) = self.next_page().await? {
         for key in keys {
-          let mut data = self.get_hashed_key(&key)?;
-          yield K::decode(&mut data)?;
+          yield decode_storage_key(&self.prefix, self.key_hash_len, &key)?;
         }
       }
     }
@@ -134,3 +187,77 @@ impl<K: Decode, V: Decode> StoragePaged<K, V> {
     }
   }
 }
+
+/// Subscription to the changes of one storage value.
+///
+/// Yields the block hash and the decoded value each time the value changes.
+pub struct StorageSubscription<T> {
+  sub: Subscription<StorageChangeSet>,
+  key: StorageKey,
+  decode: fn(Option<StorageData>) -> Result<T>,
+}
+
+impl<T> StorageSubscription<T> {
+  pub fn new(
+    sub: Subscription<StorageChangeSet>,
+    key: StorageKey,
+    decode: fn(Option<StorageData>) -> Result<T>,
+  ) -> Self {
+    Self { sub, key, decode }
+  }
+
+  /// Wait for the next change.  Returns `None` when the subscription has ended.
+  pub async fn next(&mut self) -> Option<Result<(BlockHash, T)>> {
+    core::future::poll_fn(|cx| Pin::new(&mut *self).poll_next(cx)).await
+  }
+}
+
+impl<T> Stream for StorageSubscription<T> {
+  type Item = Result<(BlockHash, T)>;
+
+  fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+    loop {
+      let set = match Pin::new(&mut self.sub).poll_next(cx) {
+        Poll::Ready(Some(Ok(set))) => set,
+        Poll::Ready(Some(Err(err))) => return Poll::Ready(Some(Err(err))),
+        Poll::Ready(None) => return Poll::Ready(None),
+        Poll::Pending => return Poll::Pending,
+      };
+      // Skip change sets without our key.
+      let change = set
+        .changes
+        .into_iter()
+        .rev()
+        .find(|(key, _)| key.0 == self.key.0);
+      if let Some((_, value)) = change {
+        let value = (self.decode)(value).map(|value| (set.block, value));
+        return Poll::Ready(Some(value));
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use codec::Encode;
+
+  #[test]
+  fn test_decode_storage_key() -> anyhow::Result<()> {
+    let prefix = StorageKey(vec![1; 32]);
+    let mut key = prefix.0.clone();
+    key.extend([0xff; 8]);
+    key.extend(42u32.encode());
+    let key = StorageKey(key);
+
+    assert_eq!(decode_storage_key::<u32>(&prefix, Some(8), &key)?, 42);
+    // Non-reversible hasher.
+    assert!(decode_storage_key::<u32>(&prefix, None, &key).is_err());
+    // Wrong prefix.
+    assert!(decode_storage_key::<u32>(&StorageKey(vec![2; 32]), Some(8), &key).is_err());
+    // Too short.
+    assert!(decode_storage_key::<u32>(&prefix, Some(8), &StorageKey(vec![1; 36])).is_err());
+    Ok(())
+  }
+}
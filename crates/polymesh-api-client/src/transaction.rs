@@ -1,12 +1,14 @@
-use jsonrpsee::core::client::Subscription;
-
 #[cfg(not(feature = "std"))]
-use alloc::format;
+use alloc::{format, sync::Arc};
 use codec::{Decode, Encode};
 use sp_std::prelude::*;
+#[cfg(feature = "std")]
+use std::sync::Arc;
 
 use async_trait::async_trait;
 
+use sp_runtime::transaction_validity::TransactionValidityError;
+
 #[cfg(feature = "serde")]
 use serde::{de::DeserializeOwned, ser::Serialize};
 
@@ -72,6 +74,60 @@ impl<Api: ChainApi> ExtrinsicResult<Api> {
   }
 }
 
+/// Result of a transaction dry-run (`ApplyExtrinsicResult`).
+#[derive(Clone)]
+pub enum DryRunResult<Api: ChainApi> {
+  /// The transaction is valid and the call would succeed.
+  Success,
+  /// The transaction is valid, but the call would fail.
+  Failed(Api::DispatchError),
+  /// The transaction is invalid and wouldn't be included in a block.
+  Invalid(TransactionValidityError),
+}
+
+impl<Api: ChainApi> DryRunResult<Api> {
+  /// Decode an encoded `ApplyExtrinsicResult`.
+  pub fn decode(data: &mut &[u8]) -> Result<Self> {
+    let result = core::result::Result::<
+      core::result::Result<(), Api::DispatchError>,
+      TransactionValidityError,
+    >::decode(data)?;
+    Ok(match result {
+      Ok(Ok(())) => Self::Success,
+      Ok(Err(err)) => Self::Failed(err),
+      Err(err) => Self::Invalid(err),
+    })
+  }
+
+  pub fn is_success(&self) -> bool {
+    matches!(self, Self::Success)
+  }
+
+  pub fn ok(&self) -> Result<()> {
+    match self {
+      Self::Success => Ok(()),
+      Self::Failed(err) => Err(Error::ExtrinsicError(format!(
+        "{}: {}",
+        err.as_name(),
+        err.as_short_doc()
+      ))),
+      Self::Invalid(err) => Err(Error::ExtrinsicError(format!(
+        "Invalid transaction: {err:?}"
+      ))),
+    }
+  }
+}
+
+impl<Api: ChainApi> core::fmt::Debug for DryRunResult<Api> {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    match self {
+      Self::Success => f.write_str("Success"),
+      Self::Failed(err) => f.debug_tuple("Failed").field(err).finish(),
+      Self::Invalid(err) => f.debug_tuple("Invalid").field(err).finish(),
+    }
+  }
+}
+
 #[async_trait]
 pub trait ChainApi: Clone {
   type RuntimeCall: RuntimeEnumTraits;
@@ -102,6 +158,15 @@ pub trait ChainApi: Clone {
 
   fn client(&self) -> &Client;
 
+  /// Subscribe to the events of new blocks.  See [`EventSubscription::new`].
+  async fn subscribe_events(&self, finalized: bool) -> Result<EventSubscription<Self::RuntimeEvent>>
+  where
+    Self: Send + Sync + 'static,
+    Self::RuntimeEvent: Send + 'static,
+  {
+    EventSubscription::new(self, finalized).await
+  }
+
   /// Submit a signed/unsigned transaction, but don't wait for it to execute.
   async fn submit_and_watch(&self, xt: ExtrinsicV4) -> Result<TransactionResults<Self>> {
     let (tx_hex, tx_hash) = xt.as_hex_and_hash();
@@ -119,6 +184,7 @@ pub struct TransactionResults<Api: ChainApi> {
   events: Option<EventRecords<Api::RuntimeEvent>>,
   extrinsic_result: Option<ExtrinsicResult<Api>>,
   finalized: bool,
+  nonce_rollback: Option<(AccountId, u32, Arc<dyn NonceRollback>)>,
 }
 
 impl<Api: ChainApi> TransactionResults<Api> {
@@ -132,9 +198,21 @@ impl<Api: ChainApi> TransactionResults<Api> {
       events: None,
       extrinsic_result: None,
       finalized: false,
+      nonce_rollback: None,
     }
   }
 
+  /// Rollback the `nonce` of `account` if the transaction is invalid or dropped.
+  pub fn with_nonce_rollback(
+    mut self,
+    account: AccountId,
+    nonce: u32,
+    rollback: Arc<dyn NonceRollback>,
+  ) -> Self {
+    self.nonce_rollback = Some((account, nonce, rollback));
+    self
+  }
+
   async fn next_status(&mut self) -> Result<bool> {
     if let Some(sub) = &mut self.sub {
       match sub.next().await {
@@ -159,6 +237,14 @@ impl<Api: ChainApi> TransactionResults<Api> {
               // The transaction is back in the pool.  Might be included in a future block.
               self.block = None;
             }
+            Dropped | Invalid => {
+              // Call failed to be included in a block.  The nonce can be reused.
+              if let Some((account, nonce, rollback)) = self.nonce_rollback.take() {
+                rollback.rollback(account, nonce);
+              }
+              self.block = None;
+              self.sub = None;
+            }
             _ => {
               // Call failed to be included in a block or finalized.
               self.block = None;
@@ -306,11 +392,24 @@ impl<Api: ChainApi> Call<Api> {
     call.into()
   }
 
+  /// The encoded call, checked against the call indices of the current runtime.
+  ///
+  /// Fails with [`Error::IncompatibleCall`] if the runtime was upgraded and the
+  /// call's pallet or call index changed.
+  pub fn encoded_checked(&self) -> Result<Encoded> {
+    let encoded = self.encoded();
+    self
+      .api
+      .client()
+      .check_call(self.call.as_name(), &encoded.0)?;
+    Ok(encoded)
+  }
+
   /// Submit the transaction unsigned.
   pub async fn submit_unsigned_and_watch(&self) -> Result<TransactionResults<Api>> {
     Ok(
       self
-        .submit_raw_xt_and_watch(ExtrinsicV4::unsigned(self.encoded()))
+        .submit_raw_xt_and_watch(ExtrinsicV4::unsigned(self.encoded_checked()?))
         .await?,
     )
   }
@@ -325,7 +424,7 @@ impl<Api: ChainApi> Call<Api> {
     // Query account nonce.
     let nonce = self.api.get_nonce(account).await?;
 
-    let encoded_call = self.encoded();
+    let encoded_call = self.encoded_checked()?;
     let (additional, era) = client.get_additional_signed(lifetime).await?;
     let extra = Extra::new(era, nonce, additional.encode_metadata_hash());
     Ok(PreparedTransaction::new(
@@ -336,6 +435,31 @@ impl<Api: ChainApi> Call<Api> {
     ))
   }
 
+  /// Estimate the fees for the transaction when signed by `account`.
+  ///
+  /// A fake signature is used, so no signer is needed.
+  pub async fn estimate_fee(&self, account: AccountId) -> Result<FeeEstimate> {
+    let client = self.api.client();
+    let encoded_call = self.encoded_checked()?;
+    // Query the fee info and details at the same block.
+    let at = client
+      .get_block_header(None)
+      .await?
+      .ok_or_else(|| Error::RpcClient("Failed to get current block".into()))?
+      .hash();
+    let nonce = client.get_account_nonce_at(&account, at).await?;
+
+    let (additional, era) = client.get_additional_signed_at(None, at).await?;
+    let extra = Extra::new(era, nonce, additional.encode_metadata_hash());
+    let sig = MultiSignature::Sr25519(sp_core::sr25519::Signature::from_raw([0u8; 64]));
+    let xt = ExtrinsicV4::signed(account, sig, extra, encoded_call);
+
+    let tx_hex = xt.to_hex();
+    let info = client.query_fee_info(tx_hex.clone(), Some(at)).await?;
+    let details = client.query_fee_details(tx_hex, Some(at)).await?;
+    Ok(FeeEstimate::new(info, details))
+  }
+
   /// Sign, submit and execute the transaction.
   pub async fn execute(&self, signer: &mut impl Signer) -> Result<TransactionResults<Api>> {
     // Sign and submit transaction.
@@ -364,30 +488,127 @@ impl<Api: ChainApi> Call<Api> {
     &self,
     signer: &mut impl Signer,
   ) -> Result<TransactionResults<Api>> {
-    let client = self.api.client();
+    let (xt, nonce) = self.sign_xt(signer).await?;
+
+    let res = match self.submit_raw_xt_and_watch(xt).await {
+      Ok(res) => res,
+      Err(err) => {
+        // The transaction was rejected, so the nonce wasn't used.
+        Self::rollback_nonce(signer, nonce);
+        return Err(err);
+      }
+    };
+
+    // Update nonce if the call was submitted.
+    signer.set_nonce(nonce + 1).await;
+
+    Ok(match signer.nonce_rollback() {
+      Some(rollback) => res.with_nonce_rollback(signer.account(), nonce, rollback),
+      None => res,
+    })
+  }
+
+  fn rollback_nonce(signer: &impl Signer, nonce: u32) {
+    if let Some(rollback) = signer.nonce_rollback() {
+      rollback.rollback(signer.account(), nonce);
+    }
+  }
+
+  /// Sign the transaction and return it with the nonce used.
+  async fn sign_xt(&self, signer: &mut impl Signer) -> Result<(ExtrinsicV4, u32)> {
     let account = signer.account();
     // Query account nonce.
-    let nonce = match signer.nonce().await {
-      Some(0) | None => self.api.get_nonce(account.clone()).await?,
+    let nonce = match signer.reserve_nonce().await {
+      Some(0) | None => self.api.get_nonce(account).await?,
       Some(nonce) => nonce,
     };
 
-    let encoded_call = self.encoded();
-    let (additional, era) = client.get_additional_signed(None).await?;
+    match self.sign_xt_with_nonce(signer, nonce, None).await {
+      Ok(xt) => Ok((xt, nonce)),
+      Err(err) => {
+        Self::rollback_nonce(signer, nonce);
+        Err(err)
+      }
+    }
+  }
+
+  /// Sign the transaction with `nonce`.  The mortal era starts at block `at` (defaults to the best block).
+  async fn sign_xt_with_nonce(
+    &self,
+    signer: &impl Signer,
+    nonce: u32,
+    at: Option<BlockHash>,
+  ) -> Result<ExtrinsicV4> {
+    let client = self.api.client();
+    let encoded_call = self.encoded_checked()?;
+    let (additional, era) = match at {
+      Some(at) => client.get_additional_signed_at(None, at).await?,
+      None => client.get_additional_signed(None).await?,
+    };
     let extra = Extra::new(era, nonce, additional.encode_metadata_hash());
     let payload = SignedPayload::new(&encoded_call, &extra, additional);
 
     let payload = payload.encode();
     let sig = signer.sign(&payload[..]).await?;
 
-    let xt = ExtrinsicV4::signed(account, sig, extra, encoded_call);
+    Ok(ExtrinsicV4::signed(
+      signer.account(),
+      sig,
+      extra,
+      encoded_call,
+    ))
+  }
 
-    let res = self.submit_raw_xt_and_watch(xt).await?;
+  /// Sign the transaction and dry-run it with `system_dryRun`, without submitting it.
+  ///
+  /// The transaction is checked against the state of block `at` (defaults to the best block).
+  /// The signer's nonce isn't updated.  For an older block, the transaction is signed
+  /// with the account's nonce and a mortal era from that block.
+  pub async fn dry_run(
+    &self,
+    signer: &mut impl Signer,
+    at: Option<BlockHash>,
+  ) -> Result<DryRunResult<Api>> {
+    // First try using a locked signer.
+    if let Some(mut signer) = signer.lock().await {
+      return self.dry_run_inner(&mut signer, at).await;
+    }
+    self.dry_run_inner(signer, at).await
+  }
 
-    // Update nonce if the call was submitted.
-    signer.set_nonce(nonce + 1).await;
+  async fn dry_run_inner(
+    &self,
+    signer: &mut impl Signer,
+    at: Option<BlockHash>,
+  ) -> Result<DryRunResult<Api>> {
+    let xt = match at {
+      Some(at) => {
+        // The signer's nonce is for the best block, use the nonce from block `at`.
+        let nonce = self
+          .api
+          .client()
+          .get_account_nonce_at(&signer.account(), at)
+          .await?;
+        self.sign_xt_with_nonce(signer, nonce, Some(at)).await?
+      }
+      None => {
+        let (xt, nonce) = self.sign_xt(signer).await?;
+        // The transaction isn't submitted, so the nonce wasn't used.
+        Self::rollback_nonce(signer, nonce);
+        xt
+      }
+    };
+    self.dry_run_raw_xt(xt, at).await
+  }
 
-    Ok(res)
+  /// Dry-run a signed transaction with `system_dryRun`.
+  pub async fn dry_run_raw_xt(
+    &self,
+    xt: ExtrinsicV4,
+    at: Option<BlockHash>,
+  ) -> Result<DryRunResult<Api>> {
+    let result = self.api.client().dry_run(xt.to_hex(), at).await?;
+    DryRunResult::decode(&mut result.as_slice())
   }
 
   /// Submit a signed/unsigned transaction, but don't wait for it to execute.
@@ -6,41 +6,118 @@ use std::path::Path;
 use proc_macro::TokenStream;
 use proc_macro_error::{abort_call_site, proc_macro_error};
 
-use darling::{FromMeta, ToTokens};
-use syn::parse_macro_input;
+use quote::ToTokens;
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::{parse_macro_input, Ident, LitStr, Token};
 
-#[derive(Clone, FromMeta)]
+#[derive(Clone, Default)]
 struct CodegenArgs {
   metadata_file: Option<String>,
+  metadata_files: Vec<String>,
   metadata_url: Option<String>,
+  pallets: Vec<String>,
+  exclude_pallets: Vec<String>,
+}
+
+/// Parse a list of strings: `["a", "b"]`.
+fn parse_str_list(input: ParseStream) -> syn::Result<Vec<String>> {
+  let content;
+  syn::bracketed!(content in input);
+  let list = Punctuated::<LitStr, Token![,]>::parse_terminated(&content)?;
+  Ok(list.iter().map(|s| s.value()).collect())
+}
+
+impl Parse for CodegenArgs {
+  fn parse(input: ParseStream) -> syn::Result<Self> {
+    let mut args = Self::default();
+    while !input.is_empty() {
+      let name: Ident = input.parse()?;
+      input.parse::<Token![=]>()?;
+      match name.to_string().as_str() {
+        "metadata_file" => {
+          args.metadata_file = Some(input.parse::<LitStr>()?.value());
+        }
+        "metadata_files" => {
+          args.metadata_files = parse_str_list(input)?;
+        }
+        "metadata_url" => {
+          args.metadata_url = Some(input.parse::<LitStr>()?.value());
+        }
+        "pallets" => {
+          args.pallets = parse_str_list(input)?;
+        }
+        "exclude_pallets" => {
+          args.exclude_pallets = parse_str_list(input)?;
+        }
+        _ => {
+          return Err(syn::Error::new(
+            name.span(),
+            format!("Unknown argument `{name}`"),
+          ));
+        }
+      }
+      if input.is_empty() {
+        break;
+      }
+      input.parse::<Token![,]>()?;
+    }
+    Ok(args)
+  }
+}
+
+fn read_metadata_file(filename: &str) -> Vec<u8> {
+  let root = env::var("CARGO_MANIFEST_DIR").unwrap_or_else(|_| ".".into());
+  let filename = Path::new(&root).join(filename);
+  let mut file = File::open(filename).unwrap_or_else(|e| abort_call_site!(e));
+  let mut buf = Vec::new();
+  file
+    .read_to_end(&mut buf)
+    .unwrap_or_else(|e| abort_call_site!(e));
+  buf
 }
 
 #[proc_macro_attribute]
 #[proc_macro_error]
 pub fn codegen_api(args: TokenStream, input: TokenStream) -> TokenStream {
-  let args = parse_macro_input!(args as syn::AttributeArgs);
+  let args = parse_macro_input!(args as CodegenArgs);
   let item = parse_macro_input!(input as syn::ItemMod);
-  let args = CodegenArgs::from_list(&args).unwrap_or_else(|e| abort_call_site!(e));
+  let mod_ident = item.ident.into_token_stream();
+  let options = polymesh_api_codegen::CodegenOptions {
+    pallets: args.pallets.clone(),
+    exclude_pallets: args.exclude_pallets.clone(),
+  };
 
   let buf = match args {
     CodegenArgs {
       metadata_file: Some(filename),
+      metadata_files,
       metadata_url: None,
-    } => {
-      let root = env::var("CARGO_MANIFEST_DIR").unwrap_or_else(|_| ".".into());
-      let filename = Path::new(&root).join(filename);
-      let mut file = File::open(filename).unwrap_or_else(|e| abort_call_site!(e));
-      let mut buf = Vec::new();
-      file
-        .read_to_end(&mut buf)
-        .unwrap_or_else(|e| abort_call_site!(e));
-      buf
+      ..
+    } if metadata_files.is_empty() => read_metadata_file(&filename),
+    CodegenArgs {
+      metadata_file: None,
+      metadata_files,
+      metadata_url: None,
+      ..
+    } if !metadata_files.is_empty() => {
+      // Generate an API for each runtime version.
+      let bufs: Vec<_> = metadata_files
+        .iter()
+        .map(|filename| read_metadata_file(filename))
+        .collect();
+      return match polymesh_api_codegen::macro_codegen_versions(&bufs, mod_ident, &options) {
+        Ok(out) => out.into(),
+        Err(err) => abort_call_site!(err),
+      };
     }
     #[cfg(feature = "download_metadata")]
     CodegenArgs {
       metadata_file: None,
+      metadata_files,
       metadata_url: Some(url),
-    } => {
+      ..
+    } if metadata_files.is_empty() => {
       let url = if &url == "POLYMESH_NODE_URL" {
         std::env::var("POLYMESH_NODE_URL").unwrap_or_else(|_| "ws://localhost:9944".into())
       } else {
@@ -69,15 +146,16 @@ pub fn codegen_api(args: TokenStream, input: TokenStream) -> TokenStream {
     CodegenArgs {
       metadata_file: None,
       metadata_url: Some(_),
+      ..
     } => {
       panic!("Support `metadata_url` disabled, add feature `download_metadata`.");
     }
     _ => {
-      panic!("Must provide either `metadata_file` or `metadata_url`, but not both.");
+      panic!("Must provide one of `metadata_file`, `metadata_files` or `metadata_url`.");
     }
   };
 
-  match polymesh_api_codegen::macro_codegen(&buf, item.ident.into_token_stream().into()) {
+  match polymesh_api_codegen::macro_codegen_with_options(&buf, mod_ident, &options) {
     Ok(out) => out.into(),
     Err(err) => abort_call_site!(err),
   }
@@ -10,6 +10,7 @@ use quote::{format_ident, quote, TokenStreamExt};
 
 use codec::Decode;
 use frame_metadata::{RuntimeMetadata, RuntimeMetadataPrefixed};
+use polymesh_api_client::metadata::metadata_spec_version;
 
 fn segments_ident(segments: &[String], import_types: bool) -> TokenStream {
   let idents: Vec<_> = segments.into_iter().map(|s| format_ident!("{s}")).collect();
@@ -66,15 +67,103 @@ impl ModuleCode {
     }
     code
   }
+
+  /// Like `gen`, but put the code of each sub-module in a file `<dir>/<name>.rs`.
+  fn gen_files(self, dir: &str, files: &mut Vec<(String, TokenStream)>) -> TokenStream {
+    let mut code = TokenStream::new();
+    for (name, sub) in self.sub_modules {
+      let ident = format_ident!("{name}");
+      let sub_code = sub.gen();
+      files.push((
+        format!("{dir}/{name}.rs"),
+        quote! {
+          use super::*;
+          #sub_code
+        },
+      ));
+      code.append_all(quote! {
+        pub mod #ident;
+      });
+    }
+    for (_, ty_code) in self.types {
+      code.append_all(ty_code);
+    }
+    code
+  }
+}
+
+/// Options for the generated code.
+#[derive(Clone, Debug, Default)]
+pub struct CodegenOptions {
+  /// Only generate the API of these pallets.  All pallets when empty.
+  ///
+  /// The `System` pallet is always included, it is needed by the client.
+  pub pallets: Vec<String>,
+  /// Don't generate the API of these pallets.  `System` can't be excluded.
+  ///
+  /// The calls and events of excluded pallets are removed from `RuntimeCall`
+  /// and `RuntimeEvent`, and types only used by them are not generated.
+  pub exclude_pallets: Vec<String>,
 }
 
 #[cfg(feature = "v14")]
 mod v14 {
   use super::*;
   use frame_metadata::v14::{
-    RuntimeMetadataV14, StorageEntryMetadata, StorageEntryModifier, StorageEntryType, StorageHasher,
+    PalletMetadata, RuntimeMetadataV14, StorageEntryMetadata, StorageEntryModifier,
+    StorageEntryType, StorageHasher,
+  };
+  use frame_metadata::v15::RuntimeApiMetadata;
+  use polymesh_api_client::{TypeHash, TypeHasher};
+  use scale_info::{
+    form::{Form, PortableForm},
+    Field, Path, PortableRegistry, Type, TypeDef, Variant,
   };
-  use scale_info::{form::PortableForm, Field, Path, Type, TypeDef, Variant};
+
+  /// The parts of the chain metadata used by the generator.
+  ///
+  /// V15 metadata uses the same pallet metadata as V14, but also has the runtime APIs.
+  pub(crate) struct ChainMetadata {
+    types: PortableRegistry,
+    pallets: Vec<PalletMetadata<PortableForm>>,
+    ty: <PortableForm as Form>::Type,
+    apis: Vec<RuntimeApiMetadata<PortableForm>>,
+  }
+
+  impl From<RuntimeMetadataV14> for ChainMetadata {
+    fn from(md: RuntimeMetadataV14) -> Self {
+      Self {
+        types: md.types,
+        pallets: md.pallets,
+        ty: md.ty,
+        apis: Vec::new(),
+      }
+    }
+  }
+
+  #[cfg(feature = "v15")]
+  impl From<frame_metadata::v15::RuntimeMetadataV15> for ChainMetadata {
+    fn from(md: frame_metadata::v15::RuntimeMetadataV15) -> Self {
+      Self {
+        types: md.types,
+        pallets: md
+          .pallets
+          .into_iter()
+          .map(|p| PalletMetadata {
+            name: p.name,
+            storage: p.storage,
+            calls: p.calls,
+            event: p.event,
+            constants: p.constants,
+            error: p.error,
+            index: p.index,
+          })
+          .collect(),
+        ty: md.ty,
+        apis: md.apis,
+      }
+    }
+  }
 
   #[derive(Default)]
   struct TypeParameters {
@@ -163,7 +252,7 @@ mod v14 {
   }
 
   struct Generator {
-    md: RuntimeMetadataV14,
+    md: ChainMetadata,
     external_modules: BTreeSet<String>,
     pallet_types: BTreeMap<u32, (String, String)>,
     max_error_size: usize,
@@ -177,10 +266,24 @@ mod v14 {
     v2_weights: bool,
     dispatch_event_info: bool,
     api_interface: TokenStream,
+    pallets: BTreeSet<String>,
+    exclude_pallets: BTreeSet<String>,
+    /// The types needed by the included pallets.  All types when `None`.
+    used_types: Option<BTreeSet<u32>>,
+  }
+
+  /// The generated code, before it is put into one module or split into files.
+  struct GeneratedCode {
+    types: ModuleCode,
+    /// The API module of each pallet.
+    pallets: Vec<(Ident, TokenStream)>,
+    runtime_apis: TokenStream,
+    /// The `Api` type and the other top-level code.
+    api: TokenStream,
   }
 
   impl Generator {
-    fn new(md: RuntimeMetadataV14) -> Self {
+    fn new(md: ChainMetadata, options: &CodegenOptions) -> Result<Self, String> {
       // Detect the chain runtime path.
       let runtime_ty = md.types.resolve(md.ty.id()).unwrap();
       let runtime_namespace = runtime_ty.path().namespace();
@@ -192,7 +295,7 @@ mod v14 {
       let call = quote! { runtime::RuntimeCall };
       let event = quote! { runtime::RuntimeEvent };
       let external_modules = BTreeSet::from_iter(
-        ["sp_version", "sp_weights", "bounded_collections"]
+        ["sp_weights", "bounded_collections"]
           .iter()
           .map(|t| t.to_string()),
       );
@@ -386,8 +489,24 @@ mod v14 {
         v2_weights: false,
         dispatch_event_info: false,
         api_interface,
+        pallets: options.pallets.iter().cloned().collect(),
+        exclude_pallets: options.exclude_pallets.iter().cloned().collect(),
+        used_types: None,
       };
 
+      // Check the pallet names.
+      for name in gen.pallets.iter().chain(&gen.exclude_pallets) {
+        if !gen.md.pallets.iter().any(|p| &p.name == name) {
+          return Err(format!("Unknown pallet: {name}"));
+        }
+      }
+      if gen.exclude_pallets.contains("System") {
+        return Err("The `System` pallet can't be excluded".into());
+      }
+      if !gen.pallets.is_empty() || !gen.exclude_pallets.is_empty() {
+        gen.used_types = Some(gen.find_used_types());
+      }
+
       // Process namespace remappings.
       gen.remap_namespaces();
 
@@ -416,7 +535,108 @@ mod v14 {
       // Rename pallet types.
       gen.rename_pallet_types();
 
-      gen
+      Ok(gen)
+    }
+
+    /// Generate the API of this pallet?
+    fn include_pallet(&self, name: &str) -> bool {
+      !self.exclude_pallets.contains(name)
+        && (self.pallets.is_empty() || name == "System" || self.pallets.contains(name))
+    }
+
+    /// Skip this variant of a runtime enum (`RuntimeCall`, `RuntimeEvent`, `OriginCaller`, ...)?
+    ///
+    /// The variants of those enums are named after the pallets.
+    fn skip_runtime_variant(&self, name: &str) -> bool {
+      !self.include_pallet(name) && self.md.pallets.iter().any(|p| p.name == name)
+    }
+
+    /// Find the types needed by the included pallets, the runtime types and the runtime APIs.
+    fn find_used_types(&self) -> BTreeSet<u32> {
+      let mut pending = Vec::new();
+      for p in self
+        .md
+        .pallets
+        .iter()
+        .filter(|p| self.include_pallet(&p.name))
+      {
+        pending.extend(p.calls.as_ref().map(|md| md.ty.id()));
+        pending.extend(p.event.as_ref().map(|md| md.ty.id()));
+        pending.extend(p.error.as_ref().map(|md| md.ty.id()));
+        pending.extend(p.constants.iter().map(|md| md.ty.id()));
+        if let Some(storage) = &p.storage {
+          for entry in &storage.entries {
+            match &entry.ty {
+              StorageEntryType::Plain(value) => {
+                pending.push(value.id());
+              }
+              StorageEntryType::Map { key, value, .. } => {
+                pending.push(key.id());
+                pending.push(value.id());
+              }
+            }
+          }
+        }
+      }
+      for api in &self.md.apis {
+        for method in &api.methods {
+          pending.extend(method.inputs.iter().map(|input| input.ty.id()));
+          pending.push(method.output.id());
+        }
+      }
+      for ty in self.md.types.types() {
+        if self.is_runtime_type(ty.ty().path()).is_some() {
+          pending.push(ty.id());
+        }
+      }
+
+      let mut used = BTreeSet::new();
+      while let Some(id) = pending.pop() {
+        if !used.insert(id) {
+          continue;
+        }
+        let ty = match self.md.types.resolve(id) {
+          Some(ty) => ty,
+          None => continue,
+        };
+        let is_runtime_type = self.is_runtime_type(ty.path()).is_some();
+        pending.extend(
+          ty.type_params()
+            .iter()
+            .filter_map(|param| param.ty().map(|ty| ty.id())),
+        );
+        match ty.type_def() {
+          TypeDef::Composite(def) => {
+            pending.extend(def.fields().iter().map(|f| f.ty().id()));
+          }
+          TypeDef::Variant(def) => {
+            for variant in def.variants() {
+              if is_runtime_type && self.skip_runtime_variant(variant.name()) {
+                continue;
+              }
+              pending.extend(variant.fields().iter().map(|f| f.ty().id()));
+            }
+          }
+          TypeDef::Sequence(def) => {
+            pending.push(def.type_param().id());
+          }
+          TypeDef::Array(def) => {
+            pending.push(def.type_param().id());
+          }
+          TypeDef::Tuple(def) => {
+            pending.extend(def.fields().iter().map(|f| f.id()));
+          }
+          TypeDef::Compact(def) => {
+            pending.push(def.type_param().id());
+          }
+          TypeDef::BitSequence(def) => {
+            pending.push(def.bit_store_type().id());
+            pending.push(def.bit_order_type().id());
+          }
+          TypeDef::Primitive(_) => (),
+        }
+      }
+      used
     }
 
     fn remap_namespaces(&mut self) {
@@ -834,9 +1054,32 @@ mod v14 {
         }
       };
 
+      let subscribe_ident = format_ident!("subscribe_{}", storage_ident);
+      let decode_value = quote! {
+        |value: Option<::polymesh_api_client::StorageData>| -> ::polymesh_api_client::error::Result<#return_ty> {
+          use ::codec::Decode;
+          let value = value
+            .map(|data| <#value_ty>::decode(&mut data.0.as_slice()))
+            .transpose()?;
+          #return_value
+        }
+      };
+      let subscribe_doc = format!(" Subscribe to changes of `{mod_prefix}.{storage_name}`.");
+
       let docs = &md.docs;
       if keys_len > 0 {
         quote! {
+          #[doc = #subscribe_doc]
+          #[cfg(not(feature = "ink"))]
+          pub async fn #subscribe_ident(&self, #keys) -> ::polymesh_api_client::error::Result<::polymesh_api_client::StorageSubscription<#return_ty>> {
+            use ::codec::Encode;
+            let mut buf = ::alloc::vec::Vec::with_capacity(512);
+            buf.extend([#(#key_prefix,)*]);
+            #hashing
+            let key = ::polymesh_api_client::StorageKey(buf);
+            self.api.client.subscribe_storage_by_key(key, #decode_value).await
+          }
+
           #(#[doc = #docs])*
           #[cfg(not(feature = "ink"))]
           pub async fn #storage_ident(&self, #keys) -> ::polymesh_api_client::error::Result<#return_ty> {
@@ -845,7 +1088,11 @@ mod v14 {
             buf.extend([#(#key_prefix,)*]);
             #hashing
             let key = ::polymesh_api_client::StorageKey(buf);
-            let value = self.api.client.get_storage_by_key(key, self.at).await?;
+            let value = if self.verify {
+              self.api.client.get_verified_storage_by_key(key, self.at).await?
+            } else {
+              self.api.client.get_storage_by_key(key, self.at).await?
+            };
             #return_value
           }
 
@@ -862,11 +1109,22 @@ mod v14 {
         }
       } else {
         quote! {
+          #[doc = #subscribe_doc]
+          #[cfg(not(feature = "ink"))]
+          pub async fn #subscribe_ident(&self) -> ::polymesh_api_client::error::Result<::polymesh_api_client::StorageSubscription<#return_ty>> {
+            let key = ::polymesh_api_client::StorageKey(::alloc::vec![#(#key_prefix,)*]);
+            self.api.client.subscribe_storage_by_key(key, #decode_value).await
+          }
+
           #(#[doc = #docs])*
           #[cfg(not(feature = "ink"))]
           pub async fn #storage_ident(&self) -> ::polymesh_api_client::error::Result<#return_ty> {
             let key = ::polymesh_api_client::StorageKey(::alloc::vec![#(#key_prefix,)*]);
-            let value = self.api.client.get_storage_by_key(key, self.at).await?;
+            let value = if self.verify {
+              self.api.client.get_verified_storage_by_key(key, self.at).await?
+            } else {
+              self.api.client.get_storage_by_key(key, self.at).await?
+            };
             #return_value
           }
 
@@ -1055,7 +1313,7 @@ mod v14 {
 
     fn gen_module(
       &self,
-      md: &frame_metadata::v14::PalletMetadata<PortableForm>,
+      md: &PalletMetadata<PortableForm>,
     ) -> (Ident, Ident, Ident, Ident, TokenStream) {
       let mod_idx = md.index;
       let mod_name = &md.name;
@@ -1099,46 +1357,45 @@ mod v14 {
       }
 
       let code = quote! {
-        pub mod #mod_ident {
-          use super::*;
 
-          #[derive(Clone)]
-          pub struct #mod_call_api<'api> {
-            api: &'api super::super::Api,
-          }
+        #[derive(Clone)]
+        pub struct #mod_call_api<'api> {
+          api: &'api super::super::Api,
+        }
 
-          impl<'api> #mod_call_api<'api> {
-            #call_fields
-          }
+        impl<'api> #mod_call_api<'api> {
+          #call_fields
+        }
 
-          impl<'api> From<&'api super::super::Api> for #mod_call_api<'api> {
-            fn from(api: &'api super::super::Api) -> Self {
-              Self { api }
-            }
+        impl<'api> From<&'api super::super::Api> for #mod_call_api<'api> {
+          fn from(api: &'api super::super::Api) -> Self {
+            Self { api }
           }
+        }
 
-          #[derive(Clone)]
-          pub struct #mod_query_api<'api> {
-            pub(crate) api: &'api super::super::Api,
-            #[cfg(not(feature = "ink"))]
-            pub(crate) at: Option<::polymesh_api_client::BlockHash>,
-          }
+        #[derive(Clone)]
+        pub struct #mod_query_api<'api> {
+          pub(crate) api: &'api super::super::Api,
+          #[cfg(not(feature = "ink"))]
+          pub(crate) at: Option<::polymesh_api_client::BlockHash>,
+          #[cfg(not(feature = "ink"))]
+          pub(crate) verify: bool,
+        }
 
-          impl<'api> #mod_query_api<'api> {
-            #query_fields
-          }
+        impl<'api> #mod_query_api<'api> {
+          #query_fields
+        }
 
-          #[derive(Clone)]
-          #[cfg(not(feature = "ink"))]
-          pub struct #mod_paged_query_api<'api> {
-            pub(crate) api: &'api super::super::Api,
-            pub(crate) at: Option<::polymesh_api_client::BlockHash>,
-          }
+        #[derive(Clone)]
+        #[cfg(not(feature = "ink"))]
+        pub struct #mod_paged_query_api<'api> {
+          pub(crate) api: &'api super::super::Api,
+          pub(crate) at: Option<::polymesh_api_client::BlockHash>,
+        }
 
-          #[cfg(not(feature = "ink"))]
-          impl<'api> #mod_paged_query_api<'api> {
-            #paged_query_fields
-          }
+        #[cfg(not(feature = "ink"))]
+        impl<'api> #mod_paged_query_api<'api> {
+          #paged_query_fields
         }
       };
       (
@@ -1150,6 +1407,102 @@ mod v14 {
       )
     }
 
+    fn gen_runtime_api_method(
+      &self,
+      api_name: &str,
+      md: &frame_metadata::v15::RuntimeApiMethodMetadata<PortableForm>,
+    ) -> (TokenStream, TokenStream) {
+      let method_name = format!("{api_name}_{}", md.name);
+      let method_ident = format_ident!("{}", md.name);
+      let api_calls = format_ident!("{}Calls", api_name);
+
+      let mut params = TokenStream::new();
+      let mut param_names = TokenStream::new();
+      let mut params_encode = TokenStream::new();
+      for param in &md.inputs {
+        let name = format_ident!("{}", param.name);
+        let type_name = self
+          .type_name(param.ty.id(), false, true)
+          .expect("Missing runtime API param type");
+        params.append_all(quote! {#name: #type_name,});
+        param_names.append_all(quote! {#name,});
+        params_encode.append_all(quote! {
+          #name.encode_to(&mut buf);
+        });
+      }
+      let output_ty = self
+        .type_name(md.output.id(), false, true)
+        .expect("Missing runtime API output type");
+
+      let docs = &md.docs;
+      let buf = if md.inputs.is_empty() {
+        quote! { let buf = ::alloc::vec::Vec::new(); }
+      } else {
+        quote! {
+          use ::codec::Encode;
+          let mut buf = ::alloc::vec::Vec::new();
+          #params_encode
+        }
+      };
+      let call = quote! {
+        #(#[doc = #docs])*
+        pub fn #method_ident(&self, #params) -> ::polymesh_api_client::RuntimeApiCall<#output_ty> {
+          #buf
+          ::polymesh_api_client::RuntimeApiCall::new(#method_name, buf)
+        }
+      };
+      let async_call = quote! {
+        #(#[doc = #docs])*
+        pub async fn #method_ident(&self, #params) -> ::polymesh_api_client::error::Result<#output_ty> {
+          #api_calls.#method_ident(#param_names).call(&self.api.client, self.at).await
+        }
+      };
+      (call, async_call)
+    }
+
+    fn gen_runtime_api(
+      &self,
+      md: &RuntimeApiMetadata<PortableForm>,
+    ) -> (Ident, Ident, Ident, TokenStream) {
+      let api_name = &md.name;
+      let api_ident = format_ident!("{}", api_name.to_snake_case());
+      let api_calls = format_ident!("{}Calls", api_name);
+      let api_struct = format_ident!("{}", api_name);
+
+      let (methods, async_methods): (Vec<_>, Vec<_>) = md
+        .methods
+        .iter()
+        .map(|method| self.gen_runtime_api_method(api_name, method))
+        .unzip();
+
+      let docs = &md.docs;
+      let code = quote! {
+        pub mod #api_ident {
+          use super::*;
+
+          #(#[doc = #docs])*
+          #[derive(Clone, Copy, Debug, Default)]
+          pub struct #api_calls;
+
+          impl #api_calls {
+            #(#methods)*
+          }
+
+          #(#[doc = #docs])*
+          #[derive(Clone)]
+          pub struct #api_struct<'api> {
+            pub(crate) api: &'api super::super::super::Api,
+            pub(crate) at: Option<::polymesh_api_client::BlockHash>,
+          }
+
+          impl<'api> #api_struct<'api> {
+            #(#async_methods)*
+          }
+        }
+      };
+      (api_ident, api_calls, api_struct, code)
+    }
+
     fn gen_struct_fields(
       &self,
       fields: &[Field<PortableForm>],
@@ -1284,6 +1637,9 @@ mod v14 {
         (None, TypeDef::Variant(enum_ty)) => {
           for variant in enum_ty.variants() {
             let top_name = variant.name();
+            if self.skip_runtime_variant(top_name) {
+              continue;
+            }
             let top_ident = format_ident!("{}", top_name);
             let fields = variant.fields().len();
             match fields {
@@ -1418,7 +1774,12 @@ mod v14 {
       let mut variants = TokenStream::new();
       let mut as_str_arms = TokenStream::new();
       let mut as_docs_arms = TokenStream::new();
-      for p in &self.md.pallets {
+      for p in self
+        .md
+        .pallets
+        .iter()
+        .filter(|p| self.include_pallet(&p.name))
+      {
         let idx = p.index;
         let mod_ident = format_ident!("{}", p.name);
         let error_ty = p.error.as_ref().and_then(|e| {
@@ -1549,7 +1910,12 @@ mod v14 {
       let mut variants = TokenStream::new();
       let mut as_str_arms = TokenStream::new();
       let mut as_docs_arms = TokenStream::new();
-      for p in &self.md.pallets {
+      for p in self
+        .md
+        .pallets
+        .iter()
+        .filter(|p| self.include_pallet(&p.name))
+      {
         let idx = p.index;
         let mod_ident = format_ident!("{}", p.name);
         let error_ty = p.error.as_ref().and_then(|e| {
@@ -1750,6 +2116,9 @@ mod v14 {
             let idx = variant.index();
             let docs = variant.docs();
             let name = variant.name();
+            if is_runtime_type && self.skip_runtime_variant(name) {
+              continue;
+            }
             let ident = format_ident!("{}", name);
             let (is_tuple, fields) = self.gen_enum_fields(variant.fields(), &mut scope)?;
             let variant_ty = if is_runtime_event {
@@ -1843,13 +2212,144 @@ mod v14 {
       Some(code)
     }
 
-    fn generate_types(&self) -> TokenStream {
+    /// Typed filters for the runtime events, one type per pallet with a filter
+    /// constant for each event variant.
+    fn gen_event_filters(&self) -> TokenStream {
+      let event_ty = &self.event;
+      let mut pallets = TokenStream::new();
+      for pallet in self
+        .md
+        .pallets
+        .iter()
+        .filter(|p| self.include_pallet(&p.name))
+      {
+        let p_name = &pallet.name;
+        let variants = match pallet
+          .event
+          .as_ref()
+          .and_then(|md| self.md.types.resolve(md.ty.id()))
+          .map(|ty| ty.type_def())
+        {
+          Some(TypeDef::Variant(enum_ty)) => enum_ty.variants(),
+          _ => continue,
+        };
+        let p_ident = format_ident!("{p_name}");
+        let alias_ident = format_ident!("{p_name}Event");
+        let all_docs = format!(" All events of the `{p_name}` pallet.");
+        let mut filters = TokenStream::new();
+        for variant in variants {
+          let name = variant.name();
+          let ident = format_ident!("{name}");
+          let docs = variant.docs();
+          let full_name = format!("{p_name}.{name}");
+          filters.append_all(quote! {
+            #(#[doc = #docs])*
+            pub const #ident: EventFilter = EventFilter::Typed(#full_name, |event| {
+              matches!(
+                event,
+                types::#event_ty::#p_ident(types::runtime::events::#alias_ident::#ident { .. })
+              )
+            });
+          });
+        }
+        pallets.append_all(quote! {
+          pub struct #p_ident;
+
+          #[allow(non_upper_case_globals)]
+          impl #p_ident {
+            #[doc = #all_docs]
+            pub const ALL: EventFilter = EventFilter::Typed(#p_name, |event| {
+              matches!(event, types::#event_ty::#p_ident(_))
+            });
+
+            #filters
+          }
+        });
+      }
+      quote! {
+        /// Typed filters for [`EventSubscription`](::polymesh_api_client::EventSubscription).
+        ///
+        /// ```ignore
+        /// use event_filters::Settlement;
+        /// let events = api.subscribe_events(true).await?.filter(Settlement::InstructionExecuted);
+        /// ```
+        #[cfg(not(feature = "ink"))]
+        pub mod event_filters {
+          use super::types;
+
+          type EventFilter = ::polymesh_api_client::EventFilter<types::#event_ty>;
+
+          #pallets
+        }
+      }
+    }
+
+    /// The type hashes of the calls, events and storage entries, used to check if the
+    /// generated API is compatible with the connected chain.
+    fn gen_api_hashes(&self) -> TokenStream {
+      let mut hasher = TypeHasher::new(&self.md.types);
+      let mut calls = Vec::new();
+      let mut events = Vec::new();
+      let mut storage = Vec::new();
+      for pallet in self
+        .md
+        .pallets
+        .iter()
+        .filter(|p| self.include_pallet(&p.name))
+      {
+        let p_name = &pallet.name;
+        if let Some(md) = &pallet.calls {
+          for (name, hash) in hasher.variant_hashes(pallet.index, md.ty.id()) {
+            calls.push((format!("{p_name}.{name}"), hash));
+          }
+        }
+        if let Some(md) = &pallet.event {
+          for (name, hash) in hasher.variant_hashes(pallet.index, md.ty.id()) {
+            events.push((format!("{p_name}.{name}"), hash));
+          }
+        }
+        if let Some(md) = &pallet.storage {
+          for entry in &md.entries {
+            storage.push((
+              format!("{}.{}", md.prefix, entry.name),
+              hasher.storage_hash(entry),
+            ));
+          }
+        }
+      }
+      let hashes_code = |hashes: Vec<(String, TypeHash)>| {
+        let entries = hashes
+          .iter()
+          .map(|(name, hash)| quote! { (#name, [#(#hash),*]) });
+        quote! { &[#(#entries),*] }
+      };
+      let calls = hashes_code(calls);
+      let events = hashes_code(events);
+      let storage = hashes_code(storage);
+      quote! {
+        /// The type hashes of the calls, events and storage entries of the metadata
+        /// this API was generated from.
+        #[cfg(not(feature = "ink"))]
+        pub const API_HASHES: ::polymesh_api_client::ApiHashes = ::polymesh_api_client::ApiHashes {
+          calls: #calls,
+          events: #events,
+          storage: #storage,
+        };
+      }
+    }
+
+    fn generate_types(&self) -> ModuleCode {
       // Start with empty namespace.
       let mut modules = ModuleCode::new("".into());
       let runtime_ns = [String::from("runtime")];
 
       for ty in self.md.types.types() {
         let ty_id = ty.id();
+        if let Some(used_types) = &self.used_types {
+          if !used_types.contains(&ty_id) {
+            continue;
+          }
+        }
         let ty = ty.ty();
         let ty_path = ty.path();
         let mut ty_ns = ty_path.namespace();
@@ -1888,10 +2388,10 @@ mod v14 {
         }
       }
 
-      modules.gen()
+      modules
     }
 
-    pub fn generate(self) -> TokenStream {
+    fn generate_code(self) -> GeneratedCode {
       let mut call_fields = TokenStream::new();
       let mut query_fields = TokenStream::new();
       let mut paged_query_fields = TokenStream::new();
@@ -1901,6 +2401,7 @@ mod v14 {
         .md
         .pallets
         .iter()
+        .filter(|m| self.include_pallet(&m.name))
         .map(|m| {
           let (ident, call_api, query_api, paged_query_api, code) = self.gen_module(m);
           call_fields.append_all(quote! {
@@ -1914,6 +2415,8 @@ mod v14 {
                 api: self.api,
                 #[cfg(not(feature = "ink"))]
                 at: self.at,
+                #[cfg(not(feature = "ink"))]
+                verify: self.verify,
               }
             }
           });
@@ -1928,11 +2431,88 @@ mod v14 {
             }
           });
 
+          (ident, code)
+        })
+        .collect();
+
+      // Generate runtime API code.
+      let mut runtime_api_calls_fields = TokenStream::new();
+      let mut runtime_api_fields = TokenStream::new();
+      let runtime_apis: Vec<_> = self
+        .md
+        .apis
+        .iter()
+        .map(|api| {
+          let (ident, api_calls, api_struct, code) = self.gen_runtime_api(api);
+          runtime_api_calls_fields.append_all(quote! {
+            pub fn #ident(&self) -> api::runtime_api::#ident::#api_calls {
+              api::runtime_api::#ident::#api_calls
+            }
+          });
+          runtime_api_fields.append_all(quote! {
+            pub fn #ident(&self) -> api::runtime_api::#ident::#api_struct<'api> {
+              api::runtime_api::#ident::#api_struct {
+                api: self.api,
+                at: self.at,
+              }
+            }
+          });
           code
         })
         .collect();
 
-      let types_code = self.generate_types();
+      // Only V15+ metadata has runtime APIs.
+      let (runtime_api_methods, runtime_api_types) = if runtime_apis.is_empty() {
+        (TokenStream::new(), TokenStream::new())
+      } else {
+        (
+          quote! {
+            /// Build typed runtime API calls.
+            #[cfg(not(feature = "ink"))]
+            pub fn runtime_api_calls(&self) -> RuntimeApiCalls {
+              RuntimeApiCalls
+            }
+
+            /// Call runtime APIs at the best block.
+            #[cfg(not(feature = "ink"))]
+            pub fn runtime_api(&self) -> RuntimeApi<'_> {
+              RuntimeApi { api: self, at: None }
+            }
+
+            /// Call runtime APIs at `block`.
+            #[cfg(not(feature = "ink"))]
+            pub fn runtime_api_at(&self, block: ::polymesh_api_client::BlockHash) -> RuntimeApi<'_> {
+              RuntimeApi { api: self, at: Some(block) }
+            }
+          },
+          quote! {
+            #[derive(Clone, Copy, Debug, Default)]
+            #[cfg(not(feature = "ink"))]
+            pub struct RuntimeApiCalls;
+
+            #[cfg(not(feature = "ink"))]
+            impl RuntimeApiCalls {
+              #runtime_api_calls_fields
+            }
+
+            #[derive(Clone)]
+            #[cfg(not(feature = "ink"))]
+            pub struct RuntimeApi<'api> {
+              api: &'api Api,
+              at: Option<::polymesh_api_client::BlockHash>,
+            }
+
+            #[cfg(not(feature = "ink"))]
+            impl<'api> RuntimeApi<'api> {
+              #runtime_api_fields
+            }
+          },
+        )
+      };
+
+      let types = self.generate_types();
+      let api_hashes = self.gen_api_hashes();
+      let event_filters = self.gen_event_filters();
 
       let dispatch_info = if self.v2_weights {
         quote! { frame_support::dispatch::DispatchInfo }
@@ -1944,21 +2524,10 @@ mod v14 {
 
       let call_ty = &self.call;
       let event_ty = &self.event;
-      quote! {
-        #[allow(dead_code, unused_imports, non_camel_case_types)]
-        pub mod types {
-          use super::WrappedCall;
-          #types_code
-        }
+      let api = quote! {
+        #api_hashes
 
-        #[allow(dead_code, unused_imports, non_camel_case_types)]
-        pub mod api {
-          use super::types;
-          use super::types::*;
-          use super::WrappedCall;
-
-          #( #modules )*
-        }
+        #event_filters
 
         #[derive(Clone)]
         pub struct Api {
@@ -1993,13 +2562,20 @@ mod v14 {
             })
           }
 
+          #[cfg(not(feature = "ink"))]
+          pub fn from_client(client: ::polymesh_api_client::Client) -> Self {
+            Self { client }
+          }
+
           pub fn call(&self) -> CallApi<'_> {
             CallApi { api: self }
           }
 
+          #runtime_api_methods
+
           #[cfg(not(feature = "ink"))]
           pub fn query(&self) -> QueryApi<'_> {
-            QueryApi { api: self, at: None }
+            QueryApi { api: self, at: None, verify: false }
           }
 
           #[cfg(feature = "ink")]
@@ -2009,7 +2585,16 @@ mod v14 {
 
           #[cfg(not(feature = "ink"))]
           pub fn query_at(&self, block: ::polymesh_api_client::BlockHash) -> QueryApi<'_> {
-            QueryApi { api: self, at: Some(block) }
+            QueryApi { api: self, at: Some(block), verify: false }
+          }
+
+          /// Query storage at `block`, verifying each value with a read proof
+          /// against the block's state root.
+          ///
+          /// Only the block hash needs to be trusted, the RPC node can be untrusted.
+          #[cfg(not(feature = "ink"))]
+          pub fn verified_query_at(&self, block: ::polymesh_api_client::BlockHash) -> QueryApi<'_> {
+            QueryApi { api: self, at: Some(block), verify: true }
           }
 
           #[cfg(not(feature = "ink"))]
@@ -2022,6 +2607,15 @@ mod v14 {
             PagedQueryApi { api: self, at: Some(block) }
           }
 
+          /// Compare the calls, events and storage entries of this API with the
+          /// metadata of the connected chain.
+          ///
+          /// Needs the `type_info` feature.
+          #[cfg(all(not(feature = "ink"), feature = "type_info"))]
+          pub fn check_compatibility(&self) -> ::polymesh_api_client::error::Result<::polymesh_api_client::CompatibilityReport> {
+            self.client.check_compatibility(&API_HASHES)
+          }
+
           #[cfg(not(feature = "ink"))]
           pub fn wrap_call(&self, call: types::#call_ty) -> ::polymesh_api_client::Result<WrappedCall> {
             Ok(WrappedCall::new(self, call))
@@ -2080,6 +2674,8 @@ mod v14 {
           #call_fields
         }
 
+        #runtime_api_types
+
         #[cfg(not(feature = "ink"))]
         pub type WrappedCall = ::polymesh_api_client::Call<Api>;
         #[cfg(not(feature = "ink"))]
@@ -2107,6 +2703,8 @@ mod v14 {
           api: &'api Api,
           #[cfg(not(feature = "ink"))]
           at: Option<::polymesh_api_client::BlockHash>,
+          #[cfg(not(feature = "ink"))]
+          verify: bool,
         }
 
         impl<'api> QueryApi<'api> {
@@ -2124,32 +2722,286 @@ mod v14 {
         impl<'api> PagedQueryApi<'api> {
           #paged_query_fields
         }
+      };
+
+      GeneratedCode {
+        types,
+        pallets: modules,
+        runtime_apis: quote! { #( #runtime_apis )* },
+        api,
+      }
+    }
+
+    /// Generate the API as one module.
+    pub fn generate(self) -> TokenStream {
+      let GeneratedCode {
+        types,
+        pallets,
+        runtime_apis,
+        api,
+      } = self.generate_code();
+      let types_code = types.gen();
+      let modules = pallets.iter().map(|(ident, code)| {
+        quote! {
+          pub mod #ident {
+            use super::*;
+            #code
+          }
+        }
+      });
+      quote! {
+        #[allow(dead_code, unused_imports, non_camel_case_types)]
+        pub mod types {
+          use super::WrappedCall;
+          #types_code
+        }
+
+        #[allow(dead_code, unused_imports, non_camel_case_types)]
+        pub mod api {
+          use super::types;
+          use super::types::*;
+          use super::WrappedCall;
+
+          #( #modules )*
+
+          /// Typed runtime API calls.
+          #[cfg(not(feature = "ink"))]
+          pub mod runtime_api {
+            use super::*;
+
+            #runtime_apis
+          }
+        }
+
+        #api
       }
     }
+
+    /// Generate the API split into files: `mod.rs`, a file for each pallet in `api/`
+    /// and a file for each top-level type namespace in `types/`.
+    pub fn generate_files(self) -> Vec<(String, TokenStream)> {
+      let GeneratedCode {
+        types,
+        pallets,
+        runtime_apis,
+        api,
+      } = self.generate_code();
+      let mut files = Vec::new();
+      let types_code = types.gen_files("types", &mut files);
+      files.push((
+        "types/mod.rs".into(),
+        quote! {
+          use super::WrappedCall;
+          #types_code
+        },
+      ));
+      let mut api_mods = TokenStream::new();
+      for (ident, code) in pallets {
+        api_mods.append_all(quote! {
+          pub mod #ident;
+        });
+        files.push((
+          format!("api/{ident}.rs"),
+          quote! {
+            use super::*;
+            #code
+          },
+        ));
+      }
+      files.push((
+        "api/runtime_api.rs".into(),
+        quote! {
+          use super::*;
+          #runtime_apis
+        },
+      ));
+      files.push((
+        "api/mod.rs".into(),
+        quote! {
+          use super::types;
+          use super::types::*;
+          use super::WrappedCall;
+
+          #api_mods
+
+          /// Typed runtime API calls.
+          #[cfg(not(feature = "ink"))]
+          pub mod runtime_api;
+        },
+      ));
+      files.push((
+        "mod.rs".into(),
+        quote! {
+          #[allow(dead_code, unused_imports, non_camel_case_types)]
+          pub mod types;
+
+          #[allow(dead_code, unused_imports, non_camel_case_types)]
+          pub mod api;
+
+          #api
+        },
+      ));
+      files
+    }
+  }
+
+  pub fn generate(
+    md: impl Into<ChainMetadata>,
+    options: &CodegenOptions,
+  ) -> Result<TokenStream, String> {
+    Ok(Generator::new(md.into(), options)?.generate())
   }
 
-  pub fn generate(md: RuntimeMetadataV14) -> TokenStream {
-    Generator::new(md).generate()
+  pub fn generate_files(
+    md: impl Into<ChainMetadata>,
+    options: &CodegenOptions,
+  ) -> Result<Vec<(String, TokenStream)>, String> {
+    Ok(Generator::new(md.into(), options)?.generate_files())
   }
 }
 
 pub fn generate(metadata: RuntimeMetadataPrefixed) -> Result<TokenStream, String> {
+  generate_with_options(metadata, &CodegenOptions::default())
+}
+
+pub fn generate_with_options(
+  metadata: RuntimeMetadataPrefixed,
+  options: &CodegenOptions,
+) -> Result<TokenStream, String> {
   match metadata.1 {
     #[cfg(feature = "v14")]
-    RuntimeMetadata::V14(v14) => Ok(v14::generate(v14)),
+    RuntimeMetadata::V14(v14) => v14::generate(v14, options),
+    #[cfg(feature = "v15")]
+    RuntimeMetadata::V15(v15) => v14::generate(v15, options),
     _ => {
       return Err(format!("Unsupported metadata version"));
     }
   }
 }
 
-pub fn macro_codegen(mut buf: &[u8], mod_ident: TokenStream) -> Result<TokenStream, String> {
+/// Generate the API split into files.
+///
+/// Returns the path (relative to the output directory) and code of each file.  The
+/// output directory is a module, with the `Api` type in `mod.rs`.
+pub fn generate_files(
+  metadata: RuntimeMetadataPrefixed,
+  options: &CodegenOptions,
+) -> Result<Vec<(String, TokenStream)>, String> {
+  match metadata.1 {
+    #[cfg(feature = "v14")]
+    RuntimeMetadata::V14(v14) => v14::generate_files(v14, options),
+    #[cfg(feature = "v15")]
+    RuntimeMetadata::V15(v15) => v14::generate_files(v15, options),
+    _ => Err("Unsupported metadata version".to_string()),
+  }
+}
+
+pub fn macro_codegen(buf: &[u8], mod_ident: TokenStream) -> Result<TokenStream, String> {
+  macro_codegen_with_options(buf, mod_ident, &CodegenOptions::default())
+}
+
+pub fn macro_codegen_with_options(
+  mut buf: &[u8],
+  mod_ident: TokenStream,
+  options: &CodegenOptions,
+) -> Result<TokenStream, String> {
   let metadata = RuntimeMetadataPrefixed::decode(&mut buf).map_err(|e| e.to_string())?;
 
-  let code = generate(metadata)?;
+  let code = generate_with_options(metadata, options)?;
   Ok(quote! {
     pub mod #mod_ident {
       #code
     }
   })
 }
+
+/// Generate an API module for each runtime version (`v<spec_version>`) and an `AnyApi`
+/// enum that selects the API matching the connected chain.
+pub fn macro_codegen_versions(
+  bufs: &[Vec<u8>],
+  mod_ident: TokenStream,
+  options: &CodegenOptions,
+) -> Result<TokenStream, String> {
+  let mut versions = BTreeMap::new();
+  for buf in bufs {
+    let metadata = RuntimeMetadataPrefixed::decode(&mut &buf[..]).map_err(|e| e.to_string())?;
+    let spec_version = metadata_spec_version(&metadata.1).map_err(|e| e.to_string())?;
+    if versions.contains_key(&spec_version) {
+      return Err(format!(
+        "Duplicate metadata for spec version {spec_version}"
+      ));
+    }
+    versions.insert(spec_version, generate_with_options(metadata, options)?);
+  }
+
+  let mut modules = TokenStream::new();
+  let mut variants = TokenStream::new();
+  let mut select = TokenStream::new();
+  let mut spec_versions = TokenStream::new();
+  let mut clients = TokenStream::new();
+  // Newest version first.
+  for (spec_version, code) in versions.iter().rev() {
+    let ident = format_ident!("v{spec_version}");
+    let variant = format_ident!("V{spec_version}");
+    modules.append_all(quote! {
+      pub mod #ident {
+        #code
+      }
+    });
+    variants.append_all(quote! {
+      #variant(#ident::Api),
+    });
+    select.append_all(quote! {
+      if spec_version >= #spec_version {
+        return Ok(Self::#variant(#ident::Api::from_client(client)));
+      }
+    });
+    spec_versions.append_all(quote! {
+      Self::#variant(_) => #spec_version,
+    });
+    clients.append_all(quote! {
+      Self::#variant(api) => ::polymesh_api_client::ChainApi::client(api),
+    });
+  }
+
+  Ok(quote! {
+    pub mod #mod_ident {
+      #modules
+
+      /// The generated API for the runtime version of the connected chain.
+      #[derive(Clone)]
+      #[cfg(not(feature = "ink"))]
+      pub enum AnyApi {
+        #variants
+      }
+
+      #[cfg(not(feature = "ink"))]
+      impl AnyApi {
+        pub async fn new(url: &str) -> ::polymesh_api_client::error::Result<Self> {
+          Self::from_client(::polymesh_api_client::Client::new(url).await?)
+        }
+
+        /// Select the API of the newest runtime version that isn't newer than the
+        /// runtime of the connected chain.
+        pub fn from_client(client: ::polymesh_api_client::Client) -> ::polymesh_api_client::error::Result<Self> {
+          let spec_version = client.get_runtime_version().spec_version;
+          #select
+          Err(::polymesh_api_client::error::Error::UnsupportedSpecVersion(spec_version))
+        }
+
+        /// The runtime spec version the selected API was generated from.
+        pub fn spec_version(&self) -> u32 {
+          match self {
+            #spec_versions
+          }
+        }
+
+        pub fn client(&self) -> &::polymesh_api_client::Client {
+          match self {
+            #clients
+          }
+        }
+      }
+    }
+  })
+}
@@ -0,0 +1,75 @@
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Result};
+
+use clap::Parser;
+
+use codec::Decode;
+use frame_metadata::RuntimeMetadataPrefixed;
+
+use polymesh_api_codegen::*;
+
+/// Generate the Rust API source code from chain metadata.
+///
+/// The output directory is a module (`<output>/mod.rs`) with a file for each pallet.
+#[derive(Parser)]
+#[command(author, version, about, long_about = None)]
+struct Cli {
+  /// SCALE encoded chain metadata file (`.meta`).
+  #[arg(short, long, conflicts_with = "url", required_unless_present = "url")]
+  file: Option<PathBuf>,
+  /// Websocket url of a node to download the chain metadata from.
+  #[arg(short, long)]
+  url: Option<String>,
+  /// Output directory.
+  #[arg(short, long)]
+  output: PathBuf,
+  /// Only generate the API of these pallets (comma separated).  `System` is always included.
+  #[arg(short, long, value_delimiter = ',')]
+  pallets: Vec<String>,
+  /// Don't generate the API of these pallets (comma separated).
+  #[arg(short = 'x', long, value_delimiter = ',')]
+  exclude_pallets: Vec<String>,
+}
+
+async fn load_metadata(cli: &Cli) -> Result<RuntimeMetadataPrefixed> {
+  match (&cli.file, &cli.url) {
+    (Some(file), _) => {
+      let buf = fs::read(file)?;
+      Ok(RuntimeMetadataPrefixed::decode(&mut buf.as_slice())?)
+    }
+    (None, Some(url)) => {
+      let client = polymesh_api_client::Client::new(url).await?;
+      client
+        .get_block_metadata(None)
+        .await?
+        .ok_or_else(|| anyhow!("Failed to get chain metadata"))
+    }
+    (None, None) => Err(anyhow!("Missing metadata `--file` or `--url`")),
+  }
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+  let cli = Cli::parse();
+
+  let metadata = load_metadata(&cli).await?;
+  let options = CodegenOptions {
+    pallets: cli.pallets.clone(),
+    exclude_pallets: cli.exclude_pallets.clone(),
+  };
+  let files = generate_files(metadata, &options).map_err(|e| anyhow!(e))?;
+
+  for (path, code) in &files {
+    let file = syn::parse2::<syn::File>(code.clone())?;
+    let path = cli.output.join(path);
+    if let Some(dir) = path.parent() {
+      fs::create_dir_all(dir)?;
+    }
+    fs::write(&path, prettyplease::unparse(&file))?;
+  }
+  println!("Wrote {} files to: {}", files.len(), cli.output.display());
+
+  Ok(())
+}
@@ -1,5 +1,5 @@
 pub use polymesh_api::{
-  client::{AccountId, AssetId, IdentityId, Signer},
+  client::{AccountId, AssetId, IdentityId, NonceRollback, Signer},
   polymesh::types::{
     polymesh_primitives::{
       secondary_key::{KeyRecord, Permissions, SecondaryKey},
@@ -49,10 +49,18 @@ impl Signer for User {
     self.primary_key.nonce().await
   }
 
+  async fn reserve_nonce(&self) -> Option<u32> {
+    self.primary_key.reserve_nonce().await
+  }
+
   async fn set_nonce(&mut self, nonce: u32) {
     self.primary_key.set_nonce(nonce).await
   }
 
+  fn nonce_rollback(&self) -> Option<std::sync::Arc<dyn NonceRollback>> {
+    self.primary_key.nonce_rollback()
+  }
+
   async fn sign(&self, msg: &[u8]) -> polymesh_api::client::Result<sp_runtime::MultiSignature> {
     Ok(self.primary_key.sign(msg).await?)
   }
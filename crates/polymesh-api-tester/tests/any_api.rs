@@ -0,0 +1,35 @@
+use anyhow::Result;
+
+use polymesh_api::client::{Error, MockNode};
+use polymesh_api::AnyApi;
+
+fn spec_file(spec_version: u32) -> String {
+  format!(
+    "{}/../../specs/polymesh_dev_spec_{spec_version}.meta",
+    env!("CARGO_MANIFEST_DIR")
+  )
+}
+
+#[tokio::test]
+async fn mock_node_any_api() -> Result<()> {
+  let node = MockNode::from_metadata_file(spec_file(7004000))?;
+  let api = AnyApi::from_client(node.client().await?)?;
+  assert!(matches!(api, AnyApi::V7004000(_)));
+
+  let node = MockNode::from_metadata_file(spec_file(8000001))?;
+  let api = AnyApi::from_client(node.client().await?)?;
+  assert_eq!(api.spec_version(), 8000001);
+  match &api {
+    AnyApi::V8000001(api) => {
+      let report = api.check_compatibility()?;
+      assert!(report.is_compatible(), "{report:?}");
+    }
+    _ => panic!("Expected the v8 API"),
+  }
+
+  // Older than all generated APIs.
+  let node = MockNode::from_metadata_file(spec_file(7003003))?;
+  let err = AnyApi::from_client(node.client().await?).err();
+  assert!(matches!(err, Some(Error::UnsupportedSpecVersion(7003003))));
+  Ok(())
+}
@@ -0,0 +1,28 @@
+use anyhow::Result;
+
+use polymesh_api_client_extras::*;
+use polymesh_api_tester::*;
+
+#[tokio::test]
+async fn force_batch_transfers() -> Result<()> {
+  let mut tester = PolymeshTester::new().await?;
+  let mut users = tester.users(&["BatchUser1", "BatchUser2"]).await?;
+  let dest = users[1].account();
+
+  let balances = tester.api.call().balances();
+  let batch = BatchBuilder::new(&tester.api)
+    .mode(BatchMode::ForceBatch)
+    .max_calls(2)
+    .call(balances.transfer_with_memo(dest.into(), ONE_POLYX, None)?)
+    // Transfer more than the user's balance.
+    .call(balances.transfer_with_memo(dest.into(), u128::MAX / 2, None)?)
+    .call(balances.transfer_with_memo(dest.into(), ONE_POLYX, None)?);
+
+  let results = batch.execute(&mut users[0]).await?;
+  println!("batch results = {results:#?}");
+  assert_eq!(results.len(), 3);
+  assert!(results[0].is_success());
+  assert!(matches!(results[1], BatchCallResult::Failed(_)));
+  assert!(results[2].is_success());
+  Ok(())
+}
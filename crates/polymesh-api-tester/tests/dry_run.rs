@@ -0,0 +1,35 @@
+use anyhow::Result;
+
+use polymesh_api::client::DryRunResult;
+use polymesh_api_client_extras::*;
+use polymesh_api_tester::*;
+
+#[tokio::test]
+async fn dry_run_transfer() -> Result<()> {
+  let mut tester = PolymeshTester::new().await?;
+  let mut users = tester.users(&["DryRunUser1", "DryRunUser2"]).await?;
+  let dest = users[1].account();
+
+  // A valid transfer.
+  let res = tester
+    .api
+    .call()
+    .balances()
+    .transfer_with_memo(dest.into(), ONE_POLYX, None)?
+    .dry_run(&mut users[0], None)
+    .await?;
+  assert!(res.is_success());
+
+  // Transfer more than the user's balance.
+  let res = tester
+    .api
+    .call()
+    .balances()
+    .transfer_with_memo(dest.into(), u128::MAX / 2, None)?
+    .dry_run(&mut users[0], None)
+    .await?;
+  println!("dry-run res = {:?}", res);
+  assert!(matches!(res, DryRunResult::Failed(_)));
+  assert!(res.ok().is_err());
+  Ok(())
+}
@@ -0,0 +1,35 @@
+use anyhow::Result;
+
+use polymesh_api::types::polymesh_common_utilities::protocol_fee::ProtocolOp;
+use polymesh_api_client_extras::*;
+use polymesh_api_tester::*;
+
+#[tokio::test]
+async fn estimate_transfer_fee() -> Result<()> {
+  let mut tester = PolymeshTester::new().await?;
+  let users = tester.users(&["FeeUser1", "FeeUser2"]).await?;
+
+  let fee = tester
+    .api
+    .call()
+    .balances()
+    .transfer_with_memo(users[1].account().into(), ONE_POLYX, None)?
+    .estimate_fee(users[0].account())
+    .await?;
+  println!("fee = {fee:#?}");
+  assert!(fee.partial_fee > 0);
+  let inclusion_fee = fee.inclusion_fee.expect("Missing inclusion fee");
+  assert_eq!(inclusion_fee.total(), fee.partial_fee);
+
+  let protocol_fee = get_protocol_fee(&tester.api, ProtocolOp::AssetCreateAsset, None).await?;
+  println!("create asset protocol fee = {protocol_fee}");
+  // The fee is the `ProtocolFee.BaseFees` scaled by the `ProtocolFee.Coefficient`.
+  let query = tester.api.query().protocol_fee();
+  let base_fee = query.base_fees(ProtocolOp::AssetCreateAsset).await?;
+  let coefficient = query.coefficient().await?;
+  assert_eq!(
+    protocol_fee,
+    base_fee * coefficient.0 as u128 / coefficient.1 as u128
+  );
+  Ok(())
+}
@@ -0,0 +1,147 @@
+use anyhow::Result;
+
+use sp_core::hashing::{blake2_128, twox_128};
+use sp_keyring::Sr25519Keyring;
+
+use polymesh_api::client::{
+  AccountId, Error, IncompatibleReason, MockNode, PairSigner, StorageKey,
+};
+use polymesh_api::{Api, ChainApi};
+
+/// The runtime version the `Api` was generated from.
+#[cfg(not(feature = "polymesh_v8"))]
+const SPEC_VERSION: u32 = 7004000;
+#[cfg(feature = "polymesh_v8")]
+const SPEC_VERSION: u32 = 8000001;
+
+/// A different runtime version.
+#[cfg(not(feature = "polymesh_v8"))]
+const OTHER_SPEC_VERSION: u32 = 8000001;
+#[cfg(feature = "polymesh_v8")]
+const OTHER_SPEC_VERSION: u32 = 7004000;
+
+fn spec_file(spec_version: u32) -> String {
+  format!(
+    "{}/../../specs/polymesh_dev_spec_{spec_version}.meta",
+    env!("CARGO_MANIFEST_DIR")
+  )
+}
+
+/// Storage key of `System.Account(account)`.
+fn system_account_key(account: &AccountId) -> StorageKey {
+  let mut key = twox_128(b"System").to_vec();
+  key.extend(twox_128(b"Account"));
+  key.extend(blake2_128(&account.0));
+  key.extend(account.0);
+  StorageKey(key)
+}
+
+#[tokio::test]
+async fn mock_node_transfer() -> Result<()> {
+  let node = MockNode::from_metadata_file(spec_file(SPEC_VERSION))?;
+  let api = Api::from_client(node.client().await?);
+
+  let mut alice = PairSigner::new(Sr25519Keyring::Alice.pair());
+  let alice_id = AccountId::from(Sr25519Keyring::Alice.to_account_id());
+  let dest = Sr25519Keyring::Bob.to_account_id().into();
+
+  // Set the nonce of Alice.
+  let mut info = api.query().system().account(alice_id).await?;
+  assert_eq!(info.nonce, 0);
+  info.nonce = 5;
+  node.set_storage_value(system_account_key(&alice_id), &info)?;
+  assert_eq!(api.query().system().account(alice_id).await?.nonce, 5);
+
+  let call = api
+    .call()
+    .balances()
+    .transfer_with_memo(dest, 1_000, None)?;
+  let mut res = call.submit_and_watch(&mut alice).await?;
+  let block = res.wait_in_block().await?;
+  assert_eq!(block, Some(node.best_block_hash()));
+
+  let submitted = node.submitted_extrinsics();
+  assert_eq!(submitted.len(), 1);
+  assert!(submitted[0].0.ends_with(&call.encoded().0));
+  Ok(())
+}
+
+#[tokio::test]
+async fn mock_node_runtime_upgrade() -> Result<()> {
+  let node = MockNode::from_metadata_file(spec_file(SPEC_VERSION))?;
+  let api = Api::from_client(node.client().await?);
+  let alice_id = AccountId::from(Sr25519Keyring::Alice.to_account_id());
+  let dest = Sr25519Keyring::Bob.to_account_id().into();
+
+  let transfer = api
+    .call()
+    .balances()
+    .transfer_with_memo(dest, 1_000, None)?;
+  transfer.prepare(alice_id, None).await?;
+
+  // Upgrade to a runtime without `Balances.transfer_with_memo`.
+  node.set_metadata(std::fs::read(spec_file(OTHER_SPEC_VERSION))?)?;
+  let client = api.client();
+  for _ in 0..100 {
+    if client.get_runtime_version().spec_version == OTHER_SPEC_VERSION {
+      break;
+    }
+    tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+  }
+  assert_eq!(
+    client.get_runtime_version().spec_version,
+    OTHER_SPEC_VERSION
+  );
+
+  let err = transfer.prepare(alice_id, None).await.unwrap_err();
+  assert!(matches!(err, Error::IncompatibleCall(_)), "{err:?}");
+  let err = transfer.estimate_fee(alice_id).await.unwrap_err();
+  assert!(matches!(err, Error::IncompatibleCall(_)), "{err:?}");
+  // Unchanged calls still work.
+  api
+    .call()
+    .system()
+    .remark(b"test".to_vec())?
+    .prepare(alice_id, None)
+    .await?;
+  Ok(())
+}
+
+#[tokio::test]
+async fn mock_node_check_compatibility() -> Result<()> {
+  let node = MockNode::from_metadata_file(spec_file(SPEC_VERSION))?;
+  let api = Api::from_client(node.client().await?);
+  let report = api.check_compatibility()?;
+  assert!(report.is_compatible(), "{report:?}");
+
+  // Connect to a chain with a different runtime.
+  let node = MockNode::from_metadata_file(spec_file(OTHER_SPEC_VERSION))?;
+  let api = Api::from_client(node.client().await?);
+  let report = api.check_compatibility()?;
+  assert_eq!(report.spec_version, OTHER_SPEC_VERSION);
+  assert!(!report.is_compatible());
+  let transfer = report
+    .calls
+    .iter()
+    .find(|c| c.name == "Balances.transfer_with_memo")
+    .expect("transfer_with_memo changed");
+  assert_eq!(transfer.reason, IncompatibleReason::Changed);
+  assert!(!report.calls.iter().any(|c| c.name == "System.remark"));
+  Ok(())
+}
+
+#[test]
+fn typed_event_filters() {
+  use polymesh_api::event_filters::{Balances, System};
+  use polymesh_api::types::{frame_system::pallet::SystemEvent, runtime::RuntimeEvent};
+
+  let event = RuntimeEvent::System(SystemEvent::CodeUpdated);
+  assert!(System::CodeUpdated.matches(&event));
+  assert!(System::ALL.matches(&event));
+  assert!(!System::ExtrinsicSuccess.matches(&event));
+  assert!(!Balances::ALL.matches(&event));
+  assert_eq!(
+    format!("{:?}", System::CodeUpdated),
+    "Typed(\"System.CodeUpdated\")"
+  );
+}
@@ -0,0 +1,50 @@
+use anyhow::Result;
+
+use polymesh_api::client::Signer;
+use polymesh_api_client_extras::*;
+use polymesh_api_tester::*;
+
+#[tokio::test]
+async fn multisig_propose_and_approve() -> Result<()> {
+  let mut tester = PolymeshTester::new().await?;
+  let mut creator = tester.user("MultiSigCreator").await?;
+  let mut signers = vec![
+    tester.new_signer_idx("MultiSigSigner", 0)?,
+    tester.new_signer_idx("MultiSigSigner", 1)?,
+  ];
+  let accounts = signers.iter().map(|s| s.account()).collect::<Vec<_>>();
+
+  // The signers pay the fees for joining the multisig.
+  for account in &accounts {
+    tester
+      .api
+      .call()
+      .balances()
+      .transfer_with_memo((*account).into(), ONE_POLYX, None)?
+      .execute(&mut creator)
+      .await?;
+  }
+
+  let mut ms = MultiSigHelper::create(&tester.api, &mut creator, accounts, 2, None).await?;
+  assert_eq!(ms.pending_signers.len(), 2);
+  for signer in &mut signers {
+    ms.accept_signer(signer).await?;
+  }
+
+  // Needs both votes.
+  let call = tester.api.call().system().remark(b"multisig".to_vec())?;
+  let proposal = ms.propose(&mut signers[0], call, None).await?;
+  assert_eq!(proposal.status, ProposalStatus::Pending);
+  let status = ms
+    .approve(&mut signers[1], proposal.proposal_id, None)
+    .await?;
+  println!("proposal status = {status:?}");
+  assert_eq!(status, ProposalStatus::Executed(Ok(())));
+
+  // Reject a proposal.
+  let call = tester.api.call().system().remark(b"rejected".to_vec())?;
+  let proposal = ms.propose(&mut signers[0], call, None).await?;
+  let status = ms.reject(&mut signers[1], proposal.proposal_id).await?;
+  assert_eq!(status, ProposalStatus::Rejected);
+  Ok(())
+}
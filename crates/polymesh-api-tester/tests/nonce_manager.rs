@@ -0,0 +1,35 @@
+use anyhow::Result;
+
+use polymesh_api::client::NonceManager;
+use polymesh_api_client_extras::*;
+use polymesh_api_tester::*;
+
+#[tokio::test]
+async fn concurrent_transfers_from_one_key() -> Result<()> {
+  let mut tester = PolymeshTester::new().await?;
+  let users = tester.users(&["NonceUser1", "NonceUser2"]).await?;
+  let dest = users[1].account();
+
+  let nonces = NonceManager::new(&tester.api);
+  let signer = nonces.signer(users[0].primary_key.clone());
+
+  // Sign and submit the transfers concurrently.
+  let mut tasks = Vec::new();
+  for _ in 0..20 {
+    let api = tester.api.clone();
+    let mut signer = signer.clone();
+    tasks.push(tokio::spawn(async move {
+      let mut res = api
+        .call()
+        .balances()
+        .transfer_with_memo(dest.into(), ONE_POLYX, None)?
+        .submit_and_watch(&mut signer)
+        .await?;
+      res.ok().await
+    }));
+  }
+  for task in tasks {
+    task.await??;
+  }
+  Ok(())
+}
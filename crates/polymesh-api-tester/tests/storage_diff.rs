@@ -0,0 +1,45 @@
+use anyhow::Result;
+
+use polymesh_api::client::ChainApi;
+use polymesh_api_client_extras::*;
+use polymesh_api_tester::*;
+
+#[tokio::test]
+async fn storage_diff_account_balance() -> Result<()> {
+  let mut tester = PolymeshTester::new().await?;
+  let mut users = tester.users(&["DiffUser1", "DiffUser2"]).await?;
+  let dest = users[1].account();
+
+  let client = tester.api.client();
+  let from_block = client
+    .get_block_header(None)
+    .await?
+    .expect("Best block header")
+    .hash();
+
+  let mut res = tester
+    .api
+    .call()
+    .balances()
+    .transfer_with_memo(dest.into(), 5 * ONE_POLYX, None)?
+    .execute(&mut users[0])
+    .await?;
+  let to_block = res.wait_in_block().await?.expect("Transaction in block");
+
+  let diff = tester
+    .api
+    .paged_query()
+    .system()
+    .account()
+    .diff(from_block, to_block)
+    .await?;
+  println!("diff = {diff:#?}");
+  assert!(diff.removed.is_empty());
+  let changed = diff
+    .changed
+    .iter()
+    .find(|entry| entry.key == dest)
+    .expect("Destination account changed");
+  assert_eq!(changed.new.data.free, changed.old.data.free + 5 * ONE_POLYX);
+  Ok(())
+}
@@ -0,0 +1,29 @@
+use anyhow::Result;
+
+use polymesh_api_client_extras::*;
+use polymesh_api_tester::*;
+
+#[tokio::test]
+async fn subscribe_account_balance() -> Result<()> {
+  let mut tester = PolymeshTester::new().await?;
+  let mut users = tester.users(&["SubUser1", "SubUser2"]).await?;
+  let dest = users[1].account();
+
+  let mut sub = tester.api.query().system().subscribe_account(dest).await?;
+  // The first notification has the current value.
+  let (_, info) = sub.next().await.expect("Subscription ended")?;
+  let start_balance = info.data.free;
+
+  let mut res = tester
+    .api
+    .call()
+    .balances()
+    .transfer_with_memo(dest.into(), 7 * ONE_POLYX, None)?
+    .execute(&mut users[0])
+    .await?;
+  res.ok().await?;
+
+  let (_, info) = sub.next().await.expect("Subscription ended")?;
+  assert_eq!(info.data.free, start_balance + 7 * ONE_POLYX);
+  Ok(())
+}
@@ -0,0 +1,159 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+
+use codec::Decode;
+
+use frame_metadata::RuntimeMetadataPrefixed;
+
+use serde_json::{json, Value};
+
+use sp_core::crypto::Ss58Codec;
+
+use polymesh_api::client::{calculate_metadata_hash, metadata, PreparedTransaction};
+use polymesh_api::types::runtime::RuntimeCall;
+
+/// Polymesh SS58 address format.
+const SS58_FORMAT: u16 = 12;
+/// POLYX token decimals and symbol, needed for the metadata hash.
+const TOKEN_DECIMALS: u8 = 6;
+const TOKEN_SYMBOL: &str = "POLYX";
+
+/// Decode the prepared transaction into human-readable JSON, for review before signing.
+pub fn transaction_details(tx: &PreparedTransaction) -> Result<Value> {
+  let call: RuntimeCall = tx
+    .call
+    .decode_as()
+    .map_err(|e| anyhow!("Failed to decode call: {e:?}"))?;
+  let account = tx
+    .account
+    .map(|account| account.to_ss58check_with_version(SS58_FORMAT.into()));
+  let additional = &tx.additional;
+  Ok(json!({
+    "account": account,
+    "call": call,
+    "era": tx.extra.era(),
+    "nonce": tx.extra.nonce(),
+    // Tips can be larger than a JSON number.
+    "tip": tx.extra.tip().to_string(),
+    "specVersion": additional.spec_version,
+    "txVersion": additional.tx_version,
+    "genesisHash": additional.genesis_hash,
+    "blockHash": additional.current_hash,
+    "metadataHash": additional.metadata_hash,
+  }))
+}
+
+/// Read a metadata file.
+///
+/// The file can have the raw SCALE encoded metadata or the hex string from `state_getMetadata`.
+fn read_metadata(path: &Path) -> Result<Vec<u8>> {
+  let data =
+    fs::read(path).map_err(|e| anyhow!("Failed to read metadata file '{path:?}': {e:?}"))?;
+  Ok(match data.strip_prefix(b"0x") {
+    Some(hex) => {
+      hex::decode(hex.trim_ascii()).map_err(|e| anyhow!("Metadata not valid hex: {e:?}"))?
+    }
+    None => data,
+  })
+}
+
+/// Get the runtime spec version from the encoded metadata.
+fn metadata_spec_version(data: &[u8]) -> Result<u32> {
+  let metadata = RuntimeMetadataPrefixed::decode(&mut &data[..])
+    .map_err(|e| anyhow!("Invalid metadata: {e:?}"))?;
+  Ok(metadata::metadata_spec_version(&metadata.1)?)
+}
+
+/// Check that the prepared transaction is for the runtime version of the metadata file.
+///
+/// If the transaction has a metadata hash (`CheckMetadataHash`), it must match the
+/// hash of the metadata file.
+pub fn check_metadata(tx: &PreparedTransaction, metadata: &Path) -> Result<()> {
+  let data = read_metadata(metadata)?;
+  let spec_version = metadata_spec_version(&data)?;
+  if tx.additional.spec_version != spec_version {
+    return Err(anyhow!(
+      "Spec version mismatch: the transaction is for {}, but the metadata is for {spec_version}",
+      tx.additional.spec_version
+    ));
+  }
+  if let Some(tx_hash) = tx.additional.metadata_hash {
+    let hash = calculate_metadata_hash(&data, TOKEN_DECIMALS, TOKEN_SYMBOL)?;
+    if tx_hash != hash {
+      return Err(anyhow!(
+        "Metadata hash mismatch: the transaction has {tx_hash:?}, but the metadata hash is {hash:?}"
+      ));
+    }
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use codec::Encode;
+
+  use polymesh_api::client::{AccountId, AdditionalSigned, Encoded, Era, Extra};
+  use polymesh_api::types::frame_system::pallet::SystemCall;
+
+  fn spec_file(spec_version: u32) -> std::path::PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+      .join("../../specs")
+      .join(format!("polymesh_dev_spec_{spec_version}.meta"))
+  }
+
+  #[test]
+  fn test_transaction_details() -> Result<()> {
+    let call = RuntimeCall::System(SystemCall::remark {
+      remark: b"test".to_vec(),
+    });
+    let additional = AdditionalSigned {
+      spec_version: 7_004_000,
+      tx_version: 7,
+      ..Default::default()
+    };
+    let tx = PreparedTransaction::new(
+      AccountId::default(),
+      additional,
+      Extra::new(Era::Immortal, 5, None),
+      Encoded(call.encode()),
+    );
+    let details = transaction_details(&tx)?;
+    assert_eq!(details["call"], serde_json::to_value(&call)?);
+    assert_eq!(details["nonce"], 5);
+    assert_eq!(details["tip"], "0");
+    assert_eq!(details["specVersion"], 7_004_000);
+
+    assert_eq!(
+      metadata_spec_version(&read_metadata(&spec_file(7_004_000))?)?,
+      7_004_000
+    );
+    check_metadata(&tx, &spec_file(7_004_000))?;
+    assert!(check_metadata(&tx, &spec_file(7_003_003)).is_err());
+    Ok(())
+  }
+
+  #[test]
+  fn test_check_metadata_hash() -> Result<()> {
+    let metadata = spec_file(8_000_001);
+    let hash = calculate_metadata_hash(&read_metadata(&metadata)?, TOKEN_DECIMALS, TOKEN_SYMBOL)?;
+    let mut tx = PreparedTransaction::new(
+      AccountId::default(),
+      AdditionalSigned {
+        spec_version: 8_000_001,
+        tx_version: 8,
+        metadata_hash: Some(hash),
+        ..Default::default()
+      },
+      Extra::new(Era::Immortal, 0, Some(Some(hash))),
+      Encoded(vec![]),
+    );
+    check_metadata(&tx, &metadata)?;
+    tx.additional.metadata_hash = Some(Default::default());
+    assert!(check_metadata(&tx, &metadata).is_err());
+    Ok(())
+  }
+}
@@ -1,6 +1,6 @@
 use std::fs::File;
-use std::io::{stdin, Read};
-use std::path::Path;
+use std::io::{stdin, BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
 
 use anyhow::{anyhow, Result};
 
@@ -11,12 +11,17 @@ use serde_json::to_string;
 use rust_decimal::prelude::*;
 
 use polymesh_api::client::{
-  AccountId, Call, ChainApi, DefaultSigner, ExtrinsicV4, PreparedTransaction,
+  verify_signature, AccountId, Call, ChainApi, DirKeystore, ExtrinsicV4, KeypairSigner,
+  KeystoreJson, KeystorePair, MultiSignature, PairSigner, PreparedTransaction, SignedPayload,
 };
 use polymesh_api::Api;
 
 use clap::{Args, Parser, Subcommand};
 
+mod decode;
+mod uos;
+use uos::{CryptoType, QrFormat};
+
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
 #[command(propagate_version = true)]
@@ -31,8 +36,13 @@ enum Commands {
   Prepare(PrepareArgs),
   /// Sign a prepared transaction offline and return a full signed transaction.
   OfflineSign(OfflineSignArgs),
+  /// Decode a prepared transaction to review it before signing.
+  #[command(alias = "inspect")]
+  Decode(DecodeArgs),
   /// Submit a signed transaction.
   Submit(SubmitArgs),
+  /// Manage a keystore directory.
+  Keystore(KeystoreArgs),
 }
 
 #[derive(Args)]
@@ -43,6 +53,15 @@ struct PrepareArgs {
   /// Websocket url for the Polymesh node.
   #[arg(short, long)]
   url: String,
+  /// Also write the transaction as UOS QR code frames to this directory, for air-gapped signers.
+  #[arg(long)]
+  qr: Option<PathBuf>,
+  /// Image format of the QR code frames.
+  #[arg(long, value_enum, default_value = "png")]
+  qr_format: QrFormat,
+  /// Crypto type of the account's key, needed by the air-gapped signer.
+  #[arg(long, value_enum, default_value = "sr25519")]
+  crypto: CryptoType,
   #[command(subcommand)]
   command: PrepareCommands,
 }
@@ -71,17 +90,51 @@ enum PrepareCommands {
 
 #[derive(Args)]
 struct OfflineSignArgs {
-  /// The secret key URI.
-  #[arg(long = "suri", value_parser = decode_signer)]
-  signer: DefaultSigner,
+  /// The secret key URI (sr25519).
+  #[arg(
+    long = "suri",
+    value_parser = decode_signer,
+    required_unless_present_any = ["keystore", "signature"],
+    conflicts_with_all = ["keystore", "signature"]
+  )]
+  signer: Option<KeystorePair>,
+  /// Encrypted keystore file (Polkadot-JS JSON format) or keystore directory.
+  ///
+  /// With a keystore directory the key for the prepared transaction's account is used.
+  #[arg(long, requires = "password_file", conflicts_with = "signature")]
+  keystore: Option<PathBuf>,
+  /// Hex encoded signature from an air-gapped signer (use '-' to read from stdin, or a filename).
+  ///
+  /// The signature must include the crypto type prefix byte.
+  #[arg(long, value_parser = decode_signature)]
+  signature: Option<MultiSignature>,
+  /// File containing the keystore password.
+  #[arg(long)]
+  password_file: Option<PathBuf>,
   /// Only return the signature, instead of the full signed transaction.
   #[arg(long)]
   only_signature: bool,
+  /// Show the decoded transaction and ask for confirmation before signing.
+  #[arg(long)]
+  confirm: bool,
+  /// Refuse to sign if the transaction's spec version or metadata hash doesn't match this metadata file.
+  #[arg(long)]
+  metadata: Option<PathBuf>,
   /// Hex encoded prepared transaction to sign (use '-' to read from stdin, or a filename).
   #[arg(value_parser = decode_prepared_transaction)]
   transaction: PreparedTransaction,
 }
 
+#[derive(Args)]
+struct DecodeArgs {
+  /// Check that the transaction's spec version and metadata hash match this metadata file.
+  #[arg(long)]
+  metadata: Option<PathBuf>,
+  /// Hex encoded prepared transaction to decode (use '-' to read from stdin, or a filename).
+  #[arg(value_parser = decode_prepared_transaction)]
+  transaction: PreparedTransaction,
+}
+
 #[derive(Args)]
 struct SubmitArgs {
   /// Websocket url for the Polymesh node.
@@ -95,6 +148,36 @@ struct SubmitArgs {
   transaction: ExtrinsicV4,
 }
 
+#[derive(Args)]
+struct KeystoreArgs {
+  /// The keystore directory.
+  #[arg(long)]
+  keystore: PathBuf,
+  #[command(subcommand)]
+  command: KeystoreCommands,
+}
+
+#[derive(Clone, Subcommand)]
+enum KeystoreCommands {
+  /// List the accounts in the keystore.
+  List,
+  /// Encrypt a key and add it to the keystore.
+  Add {
+    /// The secret key URI.
+    #[arg(long)]
+    suri: String,
+    /// The key type: sr25519, ed25519 or ecdsa.
+    #[arg(long, default_value = "sr25519")]
+    key_type: String,
+    /// File containing the password used to encrypt the key.
+    #[arg(long)]
+    password_file: PathBuf,
+    /// Name of the key.
+    #[arg(long)]
+    name: Option<String>,
+  },
+}
+
 fn string_or_file(s: &str) -> Result<String> {
   match (s, Path::new(s)) {
     ("-", _) => {
@@ -115,12 +198,18 @@ fn string_or_file(s: &str) -> Result<String> {
   }
 }
 
-fn decode_signer(s: &str) -> Result<DefaultSigner> {
+fn decode_signer(s: &str) -> Result<KeystorePair> {
   let signer =
-    DefaultSigner::from_string(s, None).map_err(|e| anyhow!("Failed to decode: {e:?}"))?;
+    KeystorePair::from_string(s, None).map_err(|e| anyhow!("Failed to decode: {e:?}"))?;
   Ok(signer)
 }
 
+fn read_password(path: &Path) -> Result<String> {
+  let password = std::fs::read_to_string(path)
+    .map_err(|e| anyhow!("Failed to read password file '{path:?}': {e:?}"))?;
+  Ok(password.trim_end_matches(['\r', '\n']).to_string())
+}
+
 fn decode_account(s: &str) -> Result<AccountId> {
   if s.starts_with("0x") {
     let mut account = AccountId::default();
@@ -144,6 +233,15 @@ fn decode_prepared_transaction(s: &str) -> Result<PreparedTransaction> {
   Ok(prepared)
 }
 
+fn decode_signature(s: &str) -> Result<MultiSignature> {
+  let s = string_or_file(s)?;
+  let off = if s.starts_with("0x") { 2 } else { 0 };
+  let buf = hex::decode(&s[off..]).map_err(|e| anyhow!("Signature not valid hex: {e:?}"))?;
+  let sig =
+    MultiSignature::decode(&mut buf.as_slice()).map_err(|e| anyhow!("Invalid signature: {e:?}"))?;
+  Ok(sig)
+}
+
 fn decode_extrinsic_v4(s: &str) -> Result<ExtrinsicV4> {
   let s = string_or_file(s)?;
   let off = if s.starts_with("0x") { 2 } else { 0 };
@@ -186,12 +284,85 @@ async fn prepare(args: PrepareArgs) -> Result<()> {
   log::info!("json = {:?}", to_string(&prepared_tx));
   let encoded = prepared_tx.encode();
   println!("0x{}", hex::encode(encoded));
+  if let Some(dir) = &args.qr {
+    let payload = uos::sign_tx_payload(&prepared_tx, args.crypto)?;
+    let frames = uos::encode_frames(&payload, uos::FRAME_SIZE)?;
+    let files = uos::write_qr_frames(&frames, dir, args.qr_format)?;
+    eprintln!("Wrote {} QR frame(s) to {dir:?}", files.len());
+  }
+  Ok(())
+}
+
+fn load_signer(args: &OfflineSignArgs) -> Result<PairSigner<KeystorePair>> {
+  match (&args.signer, &args.keystore, &args.password_file) {
+    (Some(pair), _, _) => Ok(PairSigner::new(pair.clone())),
+    (None, Some(keystore), Some(password_file)) => {
+      let password = read_password(password_file)?;
+      if keystore.is_dir() {
+        let account = args.transaction.account.ok_or_else(|| {
+          anyhow!("The prepared transaction doesn't have an account to select the key")
+        })?;
+        Ok(DirKeystore::open(keystore)?.signer(&account, &password)?)
+      } else {
+        Ok(PairSigner::from_keystore_file(keystore, &password)?)
+      }
+    }
+    _ => Err(anyhow!("Either `--suri` or `--keystore` is required")),
+  }
+}
+
+/// Add the signature from an air-gapped signer to the prepared transaction.
+fn add_signature(tx: PreparedTransaction, sig: MultiSignature) -> Result<ExtrinsicV4> {
+  let account = tx
+    .account
+    .ok_or_else(|| anyhow!("The prepared transaction doesn't have an account"))?;
+  let payload = SignedPayload::new(&tx.call, &tx.extra, tx.additional).encode();
+  if !verify_signature(&account, &sig, &payload) {
+    return Err(anyhow!("Invalid signature for account {account}"));
+  }
+  Ok(ExtrinsicV4::signed(account, sig, tx.extra, tx.call))
+}
+
+/// Ask the user to confirm.  The answer is read from the terminal, because
+/// the transaction might have been read from stdin.
+fn confirm(prompt: &str) -> Result<bool> {
+  eprint!("{prompt} [y/N] ");
+  std::io::stderr().flush()?;
+  let mut answer = String::new();
+  match File::open("/dev/tty") {
+    Ok(tty) => BufReader::new(tty).read_line(&mut answer)?,
+    Err(_) => stdin().lock().read_line(&mut answer)?,
+  };
+  Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+}
+
+fn decode_tx(args: DecodeArgs) -> Result<()> {
+  let details = decode::transaction_details(&args.transaction)?;
+  println!("{}", serde_json::to_string_pretty(&details)?);
+  if let Some(metadata) = &args.metadata {
+    decode::check_metadata(&args.transaction, metadata)?;
+  }
   Ok(())
 }
 
 async fn offline_sign(args: OfflineSignArgs) -> Result<()> {
-  let mut signer = args.signer;
-  let signed_tx = args.transaction.sign(&mut signer).await?;
+  if let Some(metadata) = &args.metadata {
+    decode::check_metadata(&args.transaction, metadata)?;
+  }
+  if args.confirm {
+    let details = decode::transaction_details(&args.transaction)?;
+    eprintln!("{}", serde_json::to_string_pretty(&details)?);
+    if !confirm("Sign this transaction?")? {
+      return Err(anyhow!("Signing cancelled"));
+    }
+  }
+  let signed_tx = match args.signature.clone() {
+    Some(sig) => add_signature(args.transaction, sig)?,
+    None => {
+      let mut signer = load_signer(&args)?;
+      args.transaction.sign(&mut signer).await?
+    }
+  };
   let encoded = if args.only_signature {
     let sig = signed_tx.signature.expect("Signed transaction");
     sig.signature.encode()
@@ -227,6 +398,31 @@ async fn submit(args: SubmitArgs) -> Result<()> {
   Ok(())
 }
 
+fn keystore(args: KeystoreArgs) -> Result<()> {
+  let keystore = DirKeystore::open(&args.keystore)?;
+  match args.command {
+    KeystoreCommands::List => {
+      for key in keystore.list()? {
+        let key_type = key.key_type().unwrap_or("unknown");
+        println!("{} {key_type} {}", key.address, key.name().unwrap_or(""));
+      }
+    }
+    KeystoreCommands::Add {
+      suri,
+      key_type,
+      password_file,
+      name,
+    } => {
+      let password = read_password(&password_file)?;
+      let pair = KeystorePair::from_suri(&key_type, &suri, None)?;
+      let path = keystore.insert(&pair, &password, name.as_deref())?;
+      let key = KeystoreJson::load(&path)?;
+      println!("Added {} to {path:?}", key.address);
+    }
+  }
+  Ok(())
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
   dotenv::dotenv().ok();
@@ -241,9 +437,15 @@ async fn main() -> Result<()> {
     Commands::OfflineSign(args) => {
       offline_sign(args).await?;
     }
+    Commands::Decode(args) => {
+      decode_tx(args)?;
+    }
     Commands::Submit(args) => {
       submit(args).await?;
     }
+    Commands::Keystore(args) => {
+      keystore(args)?;
+    }
   }
   Ok(())
 }
@@ -0,0 +1,175 @@
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+
+use codec::{Compact, Encode};
+
+use clap::ValueEnum;
+
+use qrcode::{render::unicode, Color, EcLevel, QrCode};
+
+use polymesh_api::client::PreparedTransaction;
+
+/// Substrate payload.
+const SUBSTRATE_ID: u8 = 0x53;
+/// Sign a transaction: call, extensions and genesis hash.
+const CMD_SIGN_TX: u8 = 0x02;
+/// Multi-part frame prefix.
+const MULTIPART: u8 = 0x00;
+
+/// Maximum payload bytes in each QR frame.
+pub const FRAME_SIZE: usize = 1024;
+
+/// Pixels per QR module in PNG images.
+const PNG_SCALE: usize = 4;
+/// Quiet zone around the QR code in modules.
+const QUIET_ZONE: usize = 4;
+
+/// The crypto type of the signing key.
+///
+/// ECDSA isn't supported, because the public key can't be recovered from the account.
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum CryptoType {
+  Ed25519,
+  Sr25519,
+}
+
+impl CryptoType {
+  fn id(self) -> u8 {
+    match self {
+      Self::Ed25519 => 0x00,
+      Self::Sr25519 => 0x01,
+    }
+  }
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum QrFormat {
+  /// PNG images.
+  Png,
+  /// Text files with unicode block characters.
+  Ascii,
+}
+
+/// Encode the prepared transaction as a UOS (Universal Offline Signatures) payload.
+///
+/// The payload has the call, the signed extensions (including the metadata hash)
+/// and the genesis hash, which is used by the signing device to select the network.
+pub fn sign_tx_payload(tx: &PreparedTransaction, crypto: CryptoType) -> Result<Vec<u8>> {
+  let account = tx
+    .account
+    .ok_or_else(|| anyhow!("The prepared transaction doesn't have an account"))?;
+  let mut payload = vec![SUBSTRATE_ID, crypto.id(), CMD_SIGN_TX];
+  payload.extend_from_slice(&account.0);
+  Compact(tx.call.0.len() as u32).encode_to(&mut payload);
+  payload.extend_from_slice(&tx.call.0);
+  tx.extra.encode_to(&mut payload);
+  tx.additional.encode_to(&mut payload);
+  payload.extend_from_slice(tx.additional.genesis_hash.as_bytes());
+  Ok(payload)
+}
+
+/// Split the payload into multi-part frames.
+///
+/// Each frame starts with `0x00`, the number of frames and the frame index (both u16 big-endian).
+pub fn encode_frames(payload: &[u8], frame_size: usize) -> Result<Vec<Vec<u8>>> {
+  let chunks = payload.chunks(frame_size).collect::<Vec<_>>();
+  let count =
+    u16::try_from(chunks.len()).map_err(|_| anyhow!("Payload too large for QR frames"))?;
+  Ok(
+    chunks
+      .into_iter()
+      .enumerate()
+      .map(|(idx, chunk)| {
+        let mut frame = Vec::with_capacity(5 + chunk.len());
+        frame.push(MULTIPART);
+        frame.extend_from_slice(&count.to_be_bytes());
+        frame.extend_from_slice(&(idx as u16).to_be_bytes());
+        frame.extend_from_slice(chunk);
+        frame
+      })
+      .collect(),
+  )
+}
+
+/// Write the frames as QR codes to `dir`.  Returns the files written.
+pub fn write_qr_frames(frames: &[Vec<u8>], dir: &Path, format: QrFormat) -> Result<Vec<PathBuf>> {
+  fs::create_dir_all(dir)?;
+  let mut files = Vec::with_capacity(frames.len());
+  for (idx, frame) in frames.iter().enumerate() {
+    let code = QrCode::with_error_correction_level(frame, EcLevel::L)
+      .map_err(|e| anyhow!("Failed to encode QR frame {idx}: {e:?}"))?;
+    let path = match format {
+      QrFormat::Png => {
+        let path = dir.join(format!("frame_{idx:03}.png"));
+        write_png(&code, &path)?;
+        path
+      }
+      QrFormat::Ascii => {
+        let path = dir.join(format!("frame_{idx:03}.txt"));
+        let text = code.render::<unicode::Dense1x2>().quiet_zone(true).build();
+        fs::write(&path, text)?;
+        path
+      }
+    };
+    files.push(path);
+  }
+  Ok(files)
+}
+
+fn write_png(code: &QrCode, path: &Path) -> Result<()> {
+  let width = code.width();
+  let colors = code.to_colors();
+  let size = (width + 2 * QUIET_ZONE) * PNG_SCALE;
+  let mut pixels = vec![0xFFu8; size * size];
+  for (idx, color) in colors.iter().enumerate() {
+    if *color == Color::Light {
+      continue;
+    }
+    let x = (idx % width + QUIET_ZONE) * PNG_SCALE;
+    let y = (idx / width + QUIET_ZONE) * PNG_SCALE;
+    for row in y..(y + PNG_SCALE) {
+      pixels[row * size + x..row * size + x + PNG_SCALE].fill(0);
+    }
+  }
+
+  let file = BufWriter::new(File::create(path)?);
+  let mut encoder = png::Encoder::new(file, size as u32, size as u32);
+  encoder.set_color(png::ColorType::Grayscale);
+  encoder.set_depth(png::BitDepth::Eight);
+  let mut writer = encoder.write_header()?;
+  writer.write_image_data(&pixels)?;
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_encode_frames() -> Result<()> {
+    let payload = (0..2500u32).map(|n| n as u8).collect::<Vec<_>>();
+    let frames = encode_frames(&payload, FRAME_SIZE)?;
+    assert_eq!(frames.len(), 3);
+    assert_eq!(&frames[0][..5], &[0x00, 0x00, 0x03, 0x00, 0x00]);
+    assert_eq!(&frames[2][..5], &[0x00, 0x00, 0x03, 0x00, 0x02]);
+    assert_eq!(frames[2].len(), 5 + 2500 - 2 * FRAME_SIZE);
+
+    let joined = frames
+      .iter()
+      .flat_map(|frame| frame[5..].iter().copied())
+      .collect::<Vec<_>>();
+    assert_eq!(joined, payload);
+
+    let dir = std::env::temp_dir().join(format!("polymesh-uos-{}", std::process::id()));
+    for format in [QrFormat::Png, QrFormat::Ascii] {
+      let files = write_qr_frames(&frames, &dir, format)?;
+      assert_eq!(files.len(), 3);
+      assert!(files.iter().all(|file| file.exists()));
+    }
+    fs::remove_dir_all(&dir)?;
+    Ok(())
+  }
+}
@@ -11,7 +11,9 @@ async fn main() -> Result<()> {
 
   let url = env::args().nth(1).expect("Missing ws url");
 
-  let api = Api::new(&url).await?;
+  // Reconnect and keep the subscription going if the node restarts.
+  let client = Client::new_with_reconnect(&url, Default::default()).await?;
+  let api = Api::from_client(client);
   let client = api.client();
 
   let mut sub_blocks = client.subscribe_blocks().await?;
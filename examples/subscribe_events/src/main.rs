@@ -0,0 +1,32 @@
+use std::env;
+
+use anyhow::Result;
+
+use polymesh_api::client::ChainApi;
+use polymesh_api::*;
+
+#[tokio::main]
+async fn main() -> Result<()> {
+  dotenv::dotenv().ok();
+  env_logger::init();
+
+  let url = env::args().nth(1).expect("Missing ws url");
+  // Optional event filters: `<Pallet>` or `<Pallet>.<Variant>`.
+  let filters = env::args().skip(2).collect::<Vec<_>>();
+
+  let api = Api::new(&url).await?;
+
+  let mut events = api.subscribe_events(true).await?;
+  for filter in &filters {
+    events = match filter.split_once('.') {
+      Some((pallet, variant)) => events.variant(pallet, variant),
+      None => events.pallet(filter),
+    };
+  }
+
+  while let Some((hash, number, event)) = events.next().await.transpose()? {
+    println!("{number} ({hash:?}): {}", event.name());
+  }
+
+  Ok(())
+}
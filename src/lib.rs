@@ -20,6 +20,17 @@ mod polymesh {}
 
 pub use polymesh::*;
 
+// The generated APIs of each supported runtime version.
+#[cfg(feature = "any_api")]
+#[codegen_api(metadata_files = [
+  "specs/polymesh_dev_spec_7004000.meta",
+  "specs/polymesh_dev_spec_8000001.meta",
+])]
+pub mod versions {}
+
+#[cfg(feature = "any_api")]
+pub use versions::AnyApi;
+
 // re-export core client and common types.
 #[cfg(feature = "rpc")]
 pub use polymesh_api_client as client;